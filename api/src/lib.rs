@@ -0,0 +1,17 @@
+// Types of the Corinth HTTP API, shared by the server & the Rust client
+#[macro_use]
+extern crate serde_derive;
+
+mod message;
+mod queue;
+mod response;
+
+pub use message::{EnqueueBody, Message, MessageState, NewMessage};
+pub use queue::{
+  CreateQueueOptions, QueueDeadLetterSettings, QueueInfo, QueueMeta, QueuePatchBody,
+};
+pub use response::{
+  BuildInfo, DequeueResult, EmptyResponse, EnqueueResult, ErrorResponse, ExtendResult,
+  ImportResult, InfoResult, PeekResult, QueueList, QueueListResult, QueueResult, ReadinessCheck,
+  ReadinessResult, Response, ServerInfo, StorageInfo,
+};
@@ -0,0 +1,57 @@
+use serde_json::Value;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum MessageState {
+  Pending,
+  Requeued,
+  Failed,
+}
+
+// Message as returned by the enqueue, dequeue & peek routes & as exported (one per line)
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Message {
+  pub id: String,
+  /// Unix timestamp in milliseconds
+  pub queued_at: u64,
+  /// Unix timestamp in milliseconds
+  pub updated_at: u64,
+  #[cfg_attr(feature = "openapi", schema(value_type = Object))]
+  pub item: Value,
+  pub state: MessageState,
+  pub num_requeues: u16,
+}
+
+// Message to enqueue
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct NewMessage {
+  /// Has to be a JSON object
+  #[cfg_attr(feature = "openapi", schema(value_type = Object))]
+  pub item: Value,
+  /// Messages with an ID that is still being tracked are dropped
+  pub deduplication_id: Option<String>,
+}
+
+impl NewMessage {
+  pub fn new(item: Value) -> NewMessage {
+    NewMessage {
+      item,
+      deduplication_id: None,
+    }
+  }
+
+  pub fn deduplicated(item: Value, deduplication_id: &str) -> NewMessage {
+    NewMessage {
+      item,
+      deduplication_id: Some(String::from(deduplication_id)),
+    }
+  }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct EnqueueBody {
+  pub messages: Vec<NewMessage>,
+}
@@ -0,0 +1,102 @@
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct QueueDeadLetterSettings {
+  pub name: String,
+  /// Messages are moved to the dead letter queue after that many requeues
+  pub threshold: u16,
+}
+
+// Metadata of a persistent queue, stored in its meta.json
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct QueueMeta {
+  pub created_at: u64,
+  pub last_compacted_at: u64,
+  pub num_acknowledged: u64,
+  pub num_deduplicated: u64,
+  pub num_requeued: u64,
+  // In seconds, may be fractional
+  pub requeue_time: f64,
+  pub deduplication_time: u32,
+  pub max_length: u64,
+  pub dead_letter_queue: Option<QueueDeadLetterSettings>,
+  // Lifetime counters, missing in metadata written by older versions
+  #[serde(default)]
+  pub num_enqueued: u64,
+  #[serde(default)]
+  pub num_dequeued: u64,
+  #[serde(default)]
+  pub num_dead_lettered: u64,
+  #[serde(default = "legacy_format_version")]
+  pub format_version: u32,
+}
+
+// meta.json files without format_version were written by version 1
+fn legacy_format_version() -> u32 {
+  1
+}
+
+// Queue as returned by the queue info & list routes
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct QueueInfo {
+  pub name: String,
+  /// Unix timestamp in milliseconds
+  pub created_at: u64,
+  /// Unix timestamp in milliseconds, 0 if never compacted
+  pub last_compacted_at: u64,
+  /// Amount of queued messages
+  pub size: usize,
+  /// Amount of tracked deduplication IDs
+  pub num_deduplicating: usize,
+  pub num_unacknowledged: usize,
+  pub num_acknowledged: u64,
+  pub num_deduplicated: u64,
+  /// Seconds a deduplication ID is tracked
+  pub deduplication_time: u32,
+  /// Seconds until an unacknowledged message is requeued
+  pub requeue_time: f64,
+  /// 0 if unlimited
+  pub max_length: u64,
+  /// Whether the queue is stored on disk
+  pub persistent: bool,
+  /// Approximate memory usage in bytes
+  pub memory_size: usize,
+  /// Size of the item file in bytes, null for volatile queues
+  pub disk_size: Option<u64>,
+  pub num_requeued: u64,
+  pub dead_letter: Option<QueueDeadLetterSettings>,
+}
+
+// Query of the create queue route, unset fields use the server's defaults
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[cfg_attr(
+  feature = "openapi",
+  derive(utoipa::IntoParams),
+  into_params(parameter_in = Query)
+)]
+pub struct CreateQueueOptions {
+  /// Seconds until an unacknowledged message is requeued, may be fractional (e.g. 0.5)
+  pub requeue_time: Option<f64>,
+  /// Seconds a deduplication ID is tracked
+  pub deduplication_time: Option<u32>,
+  /// Whether the queue is stored on disk
+  pub persistent: Option<bool>,
+  /// Maximum amount of queued messages, 0 for unlimited
+  pub max_length: Option<u64>,
+  /// Queue that messages are moved to once they exceed the threshold
+  pub dead_letter_queue_name: Option<String>,
+  /// Requeues after which a message is dead lettered
+  pub dead_letter_queue_threshold: Option<u16>,
+}
+
+// Body of the edit queue route, unset fields are kept
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct QueuePatchBody {
+  /// Seconds until an unacknowledged message is requeued, may be fractional (e.g. 0.5)
+  pub requeue_time: Option<f64>,
+  /// Seconds a deduplication ID is tracked
+  pub deduplication_time: Option<u32>,
+  /// Maximum amount of queued messages, 0 for unlimited
+  pub max_length: Option<u64>,
+}
@@ -0,0 +1,136 @@
+use crate::message::Message;
+use crate::queue::QueueInfo;
+
+// Body of successful responses
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Response<T> {
+  pub status: u16,
+  pub message: String,
+  pub result: T,
+}
+
+// Body of successful responses without a result
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct EmptyResponse {
+  pub status: u16,
+  pub message: String,
+  /// Always null
+  pub result: (),
+}
+
+// Body of failed responses
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ErrorResponse {
+  pub status: u16,
+  pub error: bool,
+  pub message: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct BuildInfo {
+  /// Git commit, "unknown" if not built from a git checkout
+  pub commit: String,
+  /// Unix timestamp in milliseconds
+  pub built_at: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct StorageInfo {
+  /// Version of the on-disk queue format
+  pub format_version: u32,
+  pub data_folder: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ServerInfo {
+  pub name: String,
+  pub version: String,
+  pub uptime_ms: u64,
+  pub uptime_secs: u64,
+  /// Unix timestamp in milliseconds
+  pub started_at: u64,
+  pub build: BuildInfo,
+  pub storage: StorageInfo,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct InfoResult {
+  pub info: ServerInfo,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct QueueList {
+  pub items: Vec<QueueInfo>,
+  pub length: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct QueueListResult {
+  pub queues: QueueList,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct QueueResult {
+  pub queue: QueueInfo,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct EnqueueResult {
+  pub num_enqueued: usize,
+  pub num_deduplicated: usize,
+  pub items: Vec<Message>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DequeueResult {
+  pub items: Vec<Message>,
+  pub num_items: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct PeekResult {
+  /// Null if the queue is empty
+  pub item: Option<Message>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ExtendResult {
+  /// Unix timestamp (in milliseconds) the message is requeued at if not acknowledged
+  pub requeue_at: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ImportResult {
+  pub num_imported: usize,
+  /// Messages whose id is already in the queue
+  pub num_skipped: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ReadinessCheck {
+  pub name: String,
+  pub ok: bool,
+  pub message: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ReadinessResult {
+  pub checks: Vec<ReadinessCheck>,
+}
@@ -0,0 +1,45 @@
+use std::env;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Commit the server was built from, CORINTH_GIT_COMMIT takes precedence (e.g. Docker builds without .git)
+fn git_commit() -> String {
+  if let Ok(commit) = env::var("CORINTH_GIT_COMMIT") {
+    return commit;
+  }
+  Command::new("git")
+    .args(["rev-parse", "HEAD"])
+    .output()
+    .ok()
+    .filter(|output| output.status.success())
+    .and_then(|output| String::from_utf8(output.stdout).ok())
+    .map(|commit| commit.trim().to_string())
+    .unwrap_or_else(|| String::from("unknown"))
+}
+
+// Unix timestamp of the build, SOURCE_DATE_EPOCH allows reproducible builds
+fn build_timestamp() -> u64 {
+  env::var("SOURCE_DATE_EPOCH")
+    .ok()
+    .and_then(|value| value.parse().ok())
+    .unwrap_or_else(|| {
+      SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_secs()
+    })
+}
+
+fn main() {
+  println!("cargo:rustc-env=CORINTH_GIT_COMMIT={}", git_commit());
+  println!(
+    "cargo:rustc-env=CORINTH_BUILD_TIMESTAMP={}",
+    build_timestamp()
+  );
+
+  // Only a new commit (or the overrides) updates the build info, source edits keep it
+  println!("cargo:rerun-if-changed=.git/HEAD");
+  println!("cargo:rerun-if-changed=.git/refs");
+  println!("cargo:rerun-if-env-changed=CORINTH_GIT_COMMIT");
+  println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+}
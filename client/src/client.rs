@@ -0,0 +1,442 @@
+use crate::error::ClientError;
+use crate::retry::RetryPolicy;
+use actix_rt::time::delay_for;
+use awc::error::SendRequestError;
+use awc::http::Method;
+use corinth_api::{
+  CreateQueueOptions, DequeueResult, EnqueueBody, EnqueueResult, ErrorResponse, ExtendResult,
+  ImportResult, InfoResult, Message, NewMessage, PeekResult, QueueInfo, QueueListResult,
+  QueuePatchBody, QueueResult, ReadinessCheck, ReadinessResult, Response, ServerInfo,
+};
+use log::warn;
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::time::Duration;
+
+// Exports & snapshots contain whole queues
+const MAX_RESPONSE_SIZE: usize = 1 << 30;
+
+// Characters that cannot appear in a single path segment as they are
+const PATH_SEGMENT: &AsciiSet = &CONTROLS
+  .add(b' ')
+  .add(b'"')
+  .add(b'#')
+  .add(b'%')
+  .add(b'/')
+  .add(b'<')
+  .add(b'>')
+  .add(b'?')
+  .add(b'`')
+  .add(b'{')
+  .add(b'}');
+
+enum Body {
+  Empty,
+  Json(Value),
+  JsonLines(String),
+}
+
+// Client of one Corinth server
+// Failed requests are retried according to the retry policy; non-idempotent requests
+// (like dequeueing or enqueueing without deduplication IDs) only if the server did not handle them
+#[derive(Clone)]
+pub struct Client {
+  base_url: String,
+  api_key: Option<String>,
+  http: awc::Client,
+  retry: RetryPolicy,
+}
+
+impl Client {
+  // base_url is the server's address, e.g. http://localhost:44444
+  pub fn new(base_url: &str) -> Client {
+    Client {
+      base_url: String::from(base_url.trim_end_matches('/')),
+      api_key: None,
+      http: http_client(Duration::from_secs(30)),
+      retry: RetryPolicy::default(),
+    }
+  }
+
+  // Sent as bearer token, required if the server has API keys configured
+  pub fn api_key(mut self, api_key: &str) -> Client {
+    self.api_key = Some(String::from(api_key));
+    self
+  }
+
+  pub fn retry(mut self, retry: RetryPolicy) -> Client {
+    self.retry = retry;
+    self
+  }
+
+  // Timeout of a single attempt
+  pub fn timeout(mut self, timeout: Duration) -> Client {
+    self.http = http_client(timeout);
+    self
+  }
+
+  pub async fn info(&self) -> Result<ServerInfo, ClientError> {
+    let result: InfoResult = self.call(Method::GET, "/", "", Body::Empty).await?;
+    Ok(result.info)
+  }
+
+  pub async fn live(&self) -> Result<(), ClientError> {
+    self
+      .call(Method::GET, "/health/live", "", Body::Empty)
+      .await
+  }
+
+  // Fails with status 503 if the server is not ready
+  pub async fn ready(&self) -> Result<Vec<ReadinessCheck>, ClientError> {
+    let result: ReadinessResult = self
+      .call(Method::GET, "/health/ready", "", Body::Empty)
+      .await?;
+    Ok(result.checks)
+  }
+
+  // Prometheus text format
+  pub async fn metrics(&self) -> Result<String, ClientError> {
+    let bytes = self
+      .send(Method::GET, "/metrics", "", &Body::Empty, true)
+      .await?;
+    String::from_utf8(bytes).map_err(|error| ClientError::InvalidResponse(error.to_string()))
+  }
+
+  // Tar archive of all persistent queues
+  pub async fn snapshot(&self) -> Result<Vec<u8>, ClientError> {
+    self
+      .send(Method::GET, "/snapshot", "", &Body::Empty, true)
+      .await
+  }
+
+  // Shuts the server down
+  pub async fn close(&self) -> Result<(), ClientError> {
+    self.call(Method::POST, "/close", "", Body::Empty).await
+  }
+
+  pub async fn list_queues(&self) -> Result<Vec<QueueInfo>, ClientError> {
+    let result: QueueListResult = self.call(Method::GET, "/queues", "", Body::Empty).await?;
+    Ok(result.queues.items)
+  }
+
+  pub async fn queue(&self, name: &str) -> Result<QueueInfo, ClientError> {
+    let result: QueueResult = self
+      .call(Method::GET, &queue_path(name, ""), "", Body::Empty)
+      .await?;
+    Ok(result.queue)
+  }
+
+  pub async fn create_queue(
+    &self,
+    name: &str,
+    options: &CreateQueueOptions,
+  ) -> Result<(), ClientError> {
+    let query = query_string(options)?;
+    self
+      .call(Method::PUT, &queue_path(name, ""), &query, Body::Empty)
+      .await
+  }
+
+  pub async fn edit_queue(&self, name: &str, patch: &QueuePatchBody) -> Result<(), ClientError> {
+    let body = Body::Json(to_json(patch)?);
+    self
+      .call(Method::PATCH, &queue_path(name, ""), "", body)
+      .await
+  }
+
+  pub async fn delete_queue(&self, name: &str) -> Result<(), ClientError> {
+    self
+      .call(Method::DELETE, &queue_path(name, ""), "", Body::Empty)
+      .await
+  }
+
+  pub async fn purge_queue(&self, name: &str) -> Result<(), ClientError> {
+    self
+      .call(Method::DELETE, &queue_path(name, "/purge"), "", Body::Empty)
+      .await
+  }
+
+  pub async fn compact_queue(&self, name: &str) -> Result<(), ClientError> {
+    self
+      .call_with(
+        Method::POST,
+        &queue_path(name, "/compact"),
+        "",
+        Body::Empty,
+        true,
+      )
+      .await
+  }
+
+  pub async fn enqueue(
+    &self,
+    name: &str,
+    messages: Vec<NewMessage>,
+  ) -> Result<EnqueueResult, ClientError> {
+    // Messages with deduplication IDs are dropped if they are sent again
+    let idempotent = messages
+      .iter()
+      .all(|message| message.deduplication_id.is_some());
+    let body = Body::Json(to_json(&EnqueueBody { messages })?);
+    self
+      .call_with(
+        Method::POST,
+        &queue_path(name, "/enqueue"),
+        "",
+        body,
+        idempotent,
+      )
+      .await
+  }
+
+  // Unless auto acknowledged, the messages are requeued if not acknowledged in time
+  pub async fn dequeue(
+    &self,
+    name: &str,
+    amount: u8,
+    auto_ack: bool,
+  ) -> Result<Vec<Message>, ClientError> {
+    let query = format!("?amount={}&ack={}", amount, auto_ack);
+    let result: DequeueResult = self
+      .call(
+        Method::POST,
+        &queue_path(name, "/dequeue"),
+        &query,
+        Body::Empty,
+      )
+      .await?;
+    Ok(result.items)
+  }
+
+  pub async fn peek(&self, name: &str) -> Result<Option<Message>, ClientError> {
+    let result: PeekResult = self
+      .call(Method::GET, &queue_path(name, "/peek"), "", Body::Empty)
+      .await?;
+    Ok(result.item)
+  }
+
+  pub async fn ack(&self, name: &str, message_id: &str) -> Result<(), ClientError> {
+    let path = message_path(name, message_id, "ack");
+    self
+      .call_with(Method::POST, &path, "", Body::Empty, true)
+      .await
+  }
+
+  // Postpones requeuing an unacknowledged message by the given seconds (or the queue's requeue time)
+  // Returns the time (in milliseconds) the message is requeued at now
+  pub async fn extend(
+    &self,
+    name: &str,
+    message_id: &str,
+    seconds: Option<f64>,
+  ) -> Result<u64, ClientError> {
+    let path = message_path(name, message_id, "extend");
+    let query = seconds.map_or(String::new(), |secs| format!("?seconds={}", secs));
+    let result: ExtendResult = self
+      .call_with(Method::POST, &path, &query, Body::Empty, true)
+      .await?;
+    Ok(result.requeue_at)
+  }
+
+  pub async fn export(
+    &self,
+    name: &str,
+    include_in_flight: bool,
+  ) -> Result<Vec<Message>, ClientError> {
+    let query = format!("?include_in_flight={}", include_in_flight);
+    self.export_messages(name, &query).await
+  }
+
+  // Queued messages with a queued_at timestamp (in milliseconds) of at least `queued_since`
+  pub async fn export_since(
+    &self,
+    name: &str,
+    queued_since: u64,
+  ) -> Result<Vec<Message>, ClientError> {
+    let query = format!("?queued_since={}", queued_since);
+    self.export_messages(name, &query).await
+  }
+
+  async fn export_messages(&self, name: &str, query: &str) -> Result<Vec<Message>, ClientError> {
+    let bytes = self
+      .send(
+        Method::GET,
+        &queue_path(name, "/export"),
+        query,
+        &Body::Empty,
+        true,
+      )
+      .await?;
+    String::from_utf8_lossy(&bytes)
+      .lines()
+      .filter(|line| !line.trim().is_empty())
+      .map(|line| {
+        serde_json::from_str(line).map_err(|error| ClientError::InvalidResponse(error.to_string()))
+      })
+      .collect()
+  }
+
+  // Messages whose id is already in the queue are skipped
+  pub async fn import(
+    &self,
+    name: &str,
+    messages: &[Message],
+  ) -> Result<ImportResult, ClientError> {
+    let mut lines = String::new();
+    for message in messages {
+      lines.push_str(&serde_json::to_string(message).expect("JSON stringify failed"));
+      lines.push('\n');
+    }
+    self
+      .call_with(
+        Method::POST,
+        &queue_path(name, "/import"),
+        "",
+        Body::JsonLines(lines),
+        true,
+      )
+      .await
+  }
+
+  // Sends the request & returns the response's result
+  async fn call<T: DeserializeOwned>(
+    &self,
+    method: Method,
+    path: &str,
+    query: &str,
+    body: Body,
+  ) -> Result<T, ClientError> {
+    let idempotent = method != Method::POST;
+    self.call_with(method, path, query, body, idempotent).await
+  }
+
+  // Like call, for POST requests that can be repeated safely
+  async fn call_with<T: DeserializeOwned>(
+    &self,
+    method: Method,
+    path: &str,
+    query: &str,
+    body: Body,
+    idempotent: bool,
+  ) -> Result<T, ClientError> {
+    let bytes = self.send(method, path, query, &body, idempotent).await?;
+    let response: Response<T> = serde_json::from_slice(&bytes)
+      .map_err(|error| ClientError::InvalidResponse(error.to_string()))?;
+    Ok(response.result)
+  }
+
+  // Sends the request, retrying on connection & server errors, & returns the response body
+  async fn send(
+    &self,
+    method: Method,
+    path: &str,
+    query: &str,
+    body: &Body,
+    idempotent: bool,
+  ) -> Result<Vec<u8>, ClientError> {
+    let may_retry = |error: &ClientError| {
+      if idempotent || self.retry.retry_non_idempotent {
+        error.is_retryable()
+      } else {
+        error.is_unhandled()
+      }
+    };
+    let mut retry = 0;
+    loop {
+      match self.send_once(method.clone(), path, query, body).await {
+        Err(error) if may_retry(&error) && retry < self.retry.max_retries => {
+          let delay = self.retry.delay(retry);
+          warn!(
+            "{} {} failed: {}, retrying in {} ms",
+            method,
+            path,
+            error,
+            delay.as_millis()
+          );
+          delay_for(delay).await;
+          retry += 1;
+        }
+        result => return result,
+      }
+    }
+  }
+
+  async fn send_once(
+    &self,
+    method: Method,
+    path: &str,
+    query: &str,
+    body: &Body,
+  ) -> Result<Vec<u8>, ClientError> {
+    let url = format!("{}{}{}", self.base_url, path, query);
+    let mut request = self.http.request(method, url);
+    if let Some(api_key) = &self.api_key {
+      request = request.bearer_auth(api_key);
+    }
+
+    let response = match body {
+      Body::Empty => request.send().await,
+      Body::Json(value) => request.send_json(value).await,
+      Body::JsonLines(lines) => {
+        request
+          .content_type("application/x-ndjson")
+          .send_body(lines.clone())
+          .await
+      }
+    };
+    let mut response = response.map_err(|error| match error {
+      SendRequestError::Connect(error) => ClientError::Connect(error.to_string()),
+      error => ClientError::Request(error.to_string()),
+    })?;
+    let bytes = response
+      .body()
+      .limit(MAX_RESPONSE_SIZE)
+      .await
+      .map_err(|error| ClientError::Request(error.to_string()))?;
+
+    if response.status().is_success() {
+      Ok(bytes.to_vec())
+    } else {
+      let message = serde_json::from_slice::<ErrorResponse>(&bytes)
+        .map(|error| error.message)
+        .unwrap_or_else(|_| String::from_utf8_lossy(&bytes).into_owned());
+      Err(ClientError::Api {
+        status: response.status().as_u16(),
+        message,
+      })
+    }
+  }
+}
+
+fn http_client(timeout: Duration) -> awc::Client {
+  awc::Client::builder().timeout(timeout).finish()
+}
+
+fn path_segment(segment: &str) -> String {
+  utf8_percent_encode(segment, PATH_SEGMENT).to_string()
+}
+
+fn queue_path(name: &str, suffix: &str) -> String {
+  format!("/queue/{}{}", path_segment(name), suffix)
+}
+
+fn message_path(name: &str, message_id: &str, action: &str) -> String {
+  queue_path(name, &format!("/{}/{}", path_segment(message_id), action))
+}
+
+// Unset fields are left out
+fn query_string<T: Serialize>(query: &T) -> Result<String, ClientError> {
+  let query =
+    serde_urlencoded::to_string(query).map_err(|error| ClientError::Request(error.to_string()))?;
+  if query.is_empty() {
+    Ok(query)
+  } else {
+    Ok(format!("?{}", query))
+  }
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<Value, ClientError> {
+  serde_json::to_value(value).map_err(|error| ClientError::Request(error.to_string()))
+}
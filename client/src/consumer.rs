@@ -0,0 +1,180 @@
+use crate::client::Client;
+use crate::error::ClientError;
+use actix_rt::time::delay_for;
+use corinth_api::Message;
+use futures::future::{pending, select, Either};
+use log::{debug, warn};
+use std::fmt::Display;
+use std::future::Future;
+use std::time::Duration;
+
+const MIN_EXTEND_INTERVAL: Duration = Duration::from_millis(10);
+
+// Dequeues messages of one queue & passes them to a handler, one at a time
+// While a batch is being handled, the leases of its remaining messages are extended periodically
+// Messages are acknowledged if the handler succeeds & requeued by the server once their lease runs out otherwise
+pub struct Consumer {
+  client: Client,
+  queue: String,
+  batch_size: u8,
+  poll_interval: Duration,
+  // Defaults to half the queue's requeue time, read on the first batch
+  extend_interval: Option<Duration>,
+  // Seconds to extend the lease by, defaults to the queue's requeue time
+  lease_time: Option<f64>,
+}
+
+impl Consumer {
+  pub fn new(client: Client, queue: &str) -> Consumer {
+    Consumer {
+      client,
+      queue: String::from(queue),
+      batch_size: 1,
+      poll_interval: Duration::from_secs(1),
+      extend_interval: None,
+      lease_time: None,
+    }
+  }
+
+  // Messages dequeued per request
+  pub fn batch_size(mut self, batch_size: u8) -> Consumer {
+    self.batch_size = batch_size.max(1);
+    self
+  }
+
+  // Time to wait after finding the queue empty
+  pub fn poll_interval(mut self, poll_interval: Duration) -> Consumer {
+    self.poll_interval = poll_interval;
+    self
+  }
+
+  // Time between lease extensions
+  pub fn extend_interval(mut self, extend_interval: Duration) -> Consumer {
+    self.extend_interval = Some(extend_interval);
+    self
+  }
+
+  // Seconds to extend leases by, at most u32::MAX like on the server
+  // Values that are not positive (or not a number) keep the queue's requeue time
+  pub fn lease_time(mut self, seconds: f64) -> Consumer {
+    if seconds.is_finite() && seconds > 0.0 {
+      self.lease_time = Some(seconds.min(f64::from(u32::MAX)));
+    } else {
+      warn!(
+        "Invalid lease time {}, using the queue's requeue time",
+        seconds
+      );
+      self.lease_time = None;
+    }
+    self
+  }
+
+  // Consumes messages until a request fails (after retrying)
+  pub async fn run<F, Fut, E>(&mut self, handler: F) -> Result<(), ClientError>
+  where
+    F: Fn(Message) -> Fut,
+    Fut: Future<Output = Result<(), E>>,
+    E: Display,
+  {
+    loop {
+      if self.process_batch(&handler).await? == 0 {
+        delay_for(self.poll_interval).await;
+      }
+    }
+  }
+
+  // Dequeues & handles one batch, returns the amount of dequeued messages
+  pub async fn process_batch<F, Fut, E>(&mut self, handler: &F) -> Result<usize, ClientError>
+  where
+    F: Fn(Message) -> Fut,
+    Fut: Future<Output = Result<(), E>>,
+    E: Display,
+  {
+    let extend_interval = self.resolve_extend_interval().await?;
+    let messages = self
+      .client
+      .dequeue(&self.queue, self.batch_size, false)
+      .await?;
+    let num_messages = messages.len();
+    let message_ids: Vec<String> = messages.iter().map(|message| message.id.clone()).collect();
+
+    for (index, message) in messages.into_iter().enumerate() {
+      let message_id = message.id.clone();
+      let work = Box::pin(handler(message));
+      // Messages waiting for their turn must not be requeued either
+      let extend = Box::pin(self.extend_leases(&message_ids[index..], extend_interval));
+
+      let result = match select(work, extend).await {
+        Either::Left((result, _)) => result,
+        Either::Right(_) => unreachable!("lease extension never finishes"),
+      };
+      match result {
+        Ok(()) => match self.client.ack(&self.queue, &message_id).await {
+          Ok(()) => {}
+          // The lease ran out before the message was handled, it will be delivered again
+          Err(ClientError::Api { status: 404, .. }) => {
+            warn!("Message <{}> was requeued before acknowledging", message_id)
+          }
+          Err(error) => return Err(error),
+        },
+        Err(error) => warn!(
+          "Failed to handle message <{}>, leaving it to be requeued: {}",
+          message_id, error
+        ),
+      }
+    }
+    Ok(num_messages)
+  }
+
+  async fn resolve_extend_interval(&mut self) -> Result<Duration, ClientError> {
+    if let Some(extend_interval) = self.extend_interval {
+      return Ok(extend_interval);
+    }
+    let lease_time = match self.lease_time {
+      Some(lease_time) => lease_time,
+      None => self.client.queue(&self.queue).await?.requeue_time,
+    };
+    // Don't flood the server if messages are requeued (almost) immediately
+    let extend_interval = Duration::try_from_secs_f64(lease_time / 2.0)
+      .unwrap_or_default()
+      .max(MIN_EXTEND_INTERVAL);
+    self.extend_interval = Some(extend_interval);
+    Ok(extend_interval)
+  }
+
+  // Extends the messages' leases until dropped
+  async fn extend_leases(&self, message_ids: &[String], extend_interval: Duration) {
+    let mut message_ids = message_ids.to_vec();
+    while !message_ids.is_empty() {
+      delay_for(extend_interval).await;
+      let mut remaining = Vec::new();
+      for message_id in message_ids {
+        match self
+          .client
+          .extend(&self.queue, &message_id, self.lease_time)
+          .await
+        {
+          Ok(requeue_at) => {
+            debug!(
+              "Extended lease of message <{}> until {}",
+              message_id, requeue_at
+            );
+            remaining.push(message_id);
+          }
+          // Stop once the message is gone, the next extension may succeed otherwise
+          Err(error) => {
+            warn!(
+              "Failed to extend lease of message <{}>: {}",
+              message_id, error
+            );
+            if error.status() != Some(404) {
+              remaining.push(message_id);
+            }
+          }
+        }
+      }
+      message_ids = remaining;
+    }
+    pending::<()>().await
+  }
+}
@@ -0,0 +1,55 @@
+use std::fmt;
+
+#[derive(Debug)]
+pub enum ClientError {
+  // No connection could be established, the request was not sent (connection refused...)
+  Connect(String),
+  // The request could not be sent completely or its response not be read (timeout...)
+  Request(String),
+  // The server responded with an error status
+  Api { status: u16, message: String },
+  // The response body does not have the expected shape
+  InvalidResponse(String),
+}
+
+impl ClientError {
+  // Connection problems & server errors (including 503 while starting up or shutting down)
+  // may go away on their own, client errors won't
+  pub fn is_retryable(&self) -> bool {
+    match self {
+      ClientError::Connect(_) | ClientError::Request(_) => true,
+      ClientError::Api { status, .. } => *status >= 500,
+      ClientError::InvalidResponse(_) => false,
+    }
+  }
+
+  // The server did not handle the request: it was not sent, or rejected with 503 while
+  // starting up or shutting down, so even non-idempotent requests can be retried
+  pub fn is_unhandled(&self) -> bool {
+    match self {
+      ClientError::Connect(_) => true,
+      ClientError::Api { status, .. } => *status == 503,
+      _ => false,
+    }
+  }
+
+  pub fn status(&self) -> Option<u16> {
+    match self {
+      ClientError::Api { status, .. } => Some(*status),
+      _ => None,
+    }
+  }
+}
+
+impl fmt::Display for ClientError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      ClientError::Connect(message) => write!(f, "Connection failed: {}", message),
+      ClientError::Request(message) => write!(f, "Request failed: {}", message),
+      ClientError::Api { status, message } => write!(f, "{} ({})", message, status),
+      ClientError::InvalidResponse(message) => write!(f, "Invalid response: {}", message),
+    }
+  }
+}
+
+impl std::error::Error for ClientError {}
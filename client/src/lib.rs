@@ -0,0 +1,12 @@
+// Async client of the Corinth HTTP API, running on the actix runtime
+mod client;
+mod consumer;
+mod error;
+mod retry;
+
+pub use client::Client;
+pub use consumer::Consumer;
+pub use corinth_api as api;
+pub use corinth_api::{CreateQueueOptions, Message, NewMessage, QueueInfo, QueuePatchBody};
+pub use error::ClientError;
+pub use retry::RetryPolicy;
@@ -0,0 +1,44 @@
+use std::time::Duration;
+
+// How often & how long to wait before retrying failed requests
+// The delay doubles after every attempt, up to max_delay
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+  pub max_retries: u32,
+  pub initial_delay: Duration,
+  pub max_delay: Duration,
+  // Also retry requests that may have been applied already (dequeueing, enqueueing without
+  // deduplication IDs...), so they may be applied more than once
+  // Otherwise they are only retried if the server did not handle them
+  pub retry_non_idempotent: bool,
+}
+
+impl RetryPolicy {
+  // Fails on the first error
+  pub fn none() -> RetryPolicy {
+    RetryPolicy {
+      max_retries: 0,
+      ..RetryPolicy::default()
+    }
+  }
+
+  // Delay before the given retry (starting at 0)
+  pub fn delay(&self, retry: u32) -> Duration {
+    let factor = 2u32.saturating_pow(retry);
+    self
+      .initial_delay
+      .checked_mul(factor)
+      .map_or(self.max_delay, |delay| delay.min(self.max_delay))
+  }
+}
+
+impl Default for RetryPolicy {
+  fn default() -> Self {
+    RetryPolicy {
+      max_retries: 3,
+      initial_delay: Duration::from_millis(100),
+      max_delay: Duration::from_secs(5),
+      retry_non_idempotent: false,
+    }
+  }
+}
@@ -1,15 +0,0 @@
-define({
-  "name": "Corinth",
-  "version": "0.4.0",
-  "description": "A portable Rust message queue",
-  "title": "Corinth API docs",
-  "sampleUrl": false,
-  "defaultVersion": "0.0.0",
-  "apidoc": "0.3.0",
-  "generator": {
-    "name": "apidoc",
-    "time": "2021-09-08T11:14:13.659Z",
-    "url": "https://apidocjs.com",
-    "version": "0.26.0"
-  }
-});
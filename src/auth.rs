@@ -0,0 +1,253 @@
+use crate::env::{get_acl_rules, get_admin_keys, get_data_keys};
+use actix_web::dev::ServiceRequest;
+use actix_web::http::Method;
+use actix_web::{HttpMessage, HttpRequest};
+use lazy_static::lazy_static;
+
+#[derive(Clone, Copy, PartialEq, PartialOrd, Debug)]
+pub enum Scope {
+  // Server info, API docs, dashboard & static files
+  Public,
+  // Producing & consuming messages
+  Data,
+  // Managing queues & the server
+  Admin,
+}
+
+pub enum AuthError {
+  // No or unknown API key
+  Unauthorized,
+  // Valid API key, but its scope does not cover the route
+  Forbidden,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Operation {
+  // Enqueueing messages
+  Produce,
+  // Dequeueing, peeking & acknowledging messages
+  Consume,
+  // Creating, editing & deleting queues, implies all other operations
+  Admin,
+}
+
+impl Operation {
+  fn parse(value: &str) -> Option<Operation> {
+    match value {
+      "produce" => Some(Operation::Produce),
+      "consume" => Some(Operation::Consume),
+      "admin" => Some(Operation::Admin),
+      _ => None,
+    }
+  }
+}
+
+// Grants operations on all queues whose name matches the pattern
+#[derive(Clone)]
+pub struct AclRule {
+  pattern: String,
+  operations: Vec<Operation>,
+}
+
+impl AclRule {
+  fn allows(&self, queue_name: &str, operation: Operation) -> bool {
+    glob_match(&self.pattern, queue_name)
+      && self
+        .operations
+        .iter()
+        .any(|op| *op == operation || *op == Operation::Admin)
+  }
+}
+
+pub struct ApiKey {
+  key: String,
+  scope: Scope,
+  // Keys without any rules may access every queue
+  rules: Vec<AclRule>,
+}
+
+impl ApiKey {
+  fn is_restricted(&self) -> bool {
+    !self.rules.is_empty()
+  }
+}
+
+// The API key a request was authenticated with, stored in the request extensions
+#[derive(Clone, Copy)]
+pub struct Principal(&'static ApiKey);
+
+lazy_static! {
+  static ref API_KEYS: Vec<ApiKey> = {
+    let mut keys: Vec<ApiKey> = Vec::new();
+    for (keys_of_scope, scope) in [
+      (get_admin_keys(), Scope::Admin),
+      (get_data_keys(), Scope::Data),
+    ] {
+      keys.extend(keys_of_scope.into_iter().map(|key| ApiKey {
+        key,
+        scope,
+        rules: Vec::new(),
+      }));
+    }
+
+    // The config only contains rules of known keys
+    for (key, rule) in get_acl_rules() {
+      if let Some(api_key) = keys.iter_mut().find(|api_key| api_key.key == key) {
+        api_key.rules.push(rule);
+      }
+    }
+
+    keys
+  };
+}
+
+// Parses an ACL entry of the form key:operation+operation:pattern
+pub fn parse_acl_entry(entry: &str) -> Option<(String, AclRule)> {
+  let parts: Vec<&str> = entry.split(':').collect();
+  if let [key, operations, pattern] = parts.as_slice() {
+    let operations: Option<Vec<Operation>> = operations.split('+').map(Operation::parse).collect();
+    if key.is_empty() || pattern.is_empty() {
+      return None;
+    }
+    return Some((
+      key.to_string(),
+      AclRule {
+        pattern: pattern.to_string(),
+        operations: operations?,
+      },
+    ));
+  }
+  None
+}
+
+// Matches a name against a pattern in which * matches any (possibly empty) sequence
+fn glob_match(pattern: &str, name: &str) -> bool {
+  let pattern = pattern.as_bytes();
+  let name = name.as_bytes();
+  let (mut p, mut n) = (0, 0);
+  // Position of the last * in the pattern and the name index it was tried at
+  let mut backtrack: Option<(usize, usize)> = None;
+
+  while n < name.len() {
+    if p < pattern.len() && pattern[p] == b'*' {
+      backtrack = Some((p, n));
+      p += 1;
+    } else if p < pattern.len() && pattern[p] == name[n] {
+      p += 1;
+      n += 1;
+    } else if let Some((star, tried)) = backtrack {
+      // Let the last * swallow one more character
+      p = star + 1;
+      n = tried + 1;
+      backtrack = Some((star, n));
+    } else {
+      return false;
+    }
+  }
+
+  pattern[p..].iter().all(|c| *c == b'*')
+}
+
+// Authentication is only enforced if at least one API key is configured
+pub fn auth_enabled() -> bool {
+  !API_KEYS.is_empty()
+}
+
+// Compares without returning early, so the time taken does not leak the key
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+  if a.len() != b.len() {
+    return false;
+  }
+  a.iter().zip(b.iter()).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn find_key(key: &str) -> Option<&'static ApiKey> {
+  API_KEYS
+    .iter()
+    .find(|api_key| constant_time_eq(api_key.key.as_bytes(), key.as_bytes()))
+}
+
+// Returns the scope a route requires
+// Routes that are not explicitly public or data routes require the admin scope
+pub fn required_scope(method: &Method, path: &str) -> Scope {
+  let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+  match segments.as_slice() {
+    [""] | ["favicon.ico"] | ["dashboard", ..] | ["assets", ..] | ["health", _] => Scope::Public,
+    ["docs"] | ["swagger-ui", _] | ["openapi.json"] if method == Method::GET => Scope::Public,
+    ["queues"] | ["metrics"] if method == Method::GET => Scope::Data,
+    ["queue", _] if method == Method::GET => Scope::Data,
+    ["queue", _, "enqueue"] | ["queue", _, "dequeue"] if method == Method::POST => Scope::Data,
+    ["queue", _, "peek"] if method == Method::GET => Scope::Data,
+    ["queue", _, _, "ack"] | ["queue", _, _, "extend"] if method == Method::POST => Scope::Data,
+    _ => Scope::Admin,
+  }
+}
+
+fn bearer_token(req: &ServiceRequest) -> Option<&str> {
+  let header = req.headers().get("Authorization")?.to_str().ok()?;
+  let token = header.strip_prefix("Bearer ")?;
+  Some(token.trim())
+}
+
+fn is_queue_route(path: &str) -> bool {
+  path.trim_start_matches('/').starts_with("queue/")
+}
+
+// Checks the request's bearer API key against the scope of the requested route
+// The key is stored in the request extensions, so handlers can check queue ACLs
+pub fn authorize(req: &ServiceRequest) -> Result<(), AuthError> {
+  if !auth_enabled() {
+    return Ok(());
+  }
+
+  let required = required_scope(req.method(), req.path());
+  if required == Scope::Public {
+    return Ok(());
+  }
+
+  let api_key = bearer_token(req)
+    .and_then(find_key)
+    .ok_or(AuthError::Unauthorized)?;
+
+  if api_key.scope < required {
+    return Err(AuthError::Forbidden);
+  }
+  // Keys restricted to some queues may not use server-wide admin routes (snapshots etc.)
+  if api_key.is_restricted() && required == Scope::Admin && !is_queue_route(req.path()) {
+    return Err(AuthError::Forbidden);
+  }
+  // Metrics cover all queues, not only the ones the key may see
+  if api_key.is_restricted() && req.path().trim_matches('/') == "metrics" {
+    return Err(AuthError::Forbidden);
+  }
+
+  req.extensions_mut().insert(Principal(api_key));
+  Ok(())
+}
+
+fn principal(req: &HttpRequest) -> Option<&'static ApiKey> {
+  req
+    .extensions()
+    .get::<Principal>()
+    .map(|principal| principal.0)
+}
+
+// Checks the queue ACL of the request's API key
+// Always true if authentication is disabled or the key is not restricted
+pub fn can_access_queue(req: &HttpRequest, queue_name: &str, operation: Operation) -> bool {
+  match principal(req) {
+    Some(api_key) if api_key.is_restricted() => api_key
+      .rules
+      .iter()
+      .any(|rule| rule.allows(queue_name, operation)),
+    _ => true,
+  }
+}
+
+// A queue is visible if the request's API key may perform any operation on it
+pub fn can_see_queue(req: &HttpRequest, queue_name: &str) -> bool {
+  [Operation::Produce, Operation::Consume, Operation::Admin]
+    .iter()
+    .any(|operation| can_access_queue(req, queue_name, *operation))
+}
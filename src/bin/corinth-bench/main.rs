@@ -0,0 +1,176 @@
+// Load generator for the queue engine, without the HTTP layer
+// Runs producer/consumer workloads for each combination of storage, deduplication & batch size
+mod report;
+mod workload;
+
+use corinth::{Broker, BrokerOptions};
+use report::print_table;
+use std::fs::{create_dir_all, remove_dir_all};
+use std::path::PathBuf;
+use std::process;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use structopt::StructOpt;
+use workload::{Storage, Workload};
+
+impl FromStr for Storage {
+  type Err = String;
+
+  fn from_str(value: &str) -> Result<Self, Self::Err> {
+    match value {
+      "volatile" => Ok(Storage::Volatile),
+      "persistent" => Ok(Storage::Persistent),
+      _ => Err(format!("Invalid storage: {}", value)),
+    }
+  }
+}
+
+#[derive(StructOpt)]
+#[structopt(
+  name = "corinth-bench",
+  about = "Measures throughput & latency of the queue engine under producer/consumer workloads"
+)]
+struct Cli {
+  /// Messages per workload
+  #[structopt(short = "n", long, default_value = "10000")]
+  messages: u64,
+
+  /// Producer threads per workload
+  #[structopt(short, long, default_value = "2")]
+  producers: u64,
+
+  /// Consumer threads per workload
+  #[structopt(short, long, default_value = "2")]
+  consumers: u64,
+
+  /// Messages per enqueue & dequeue call
+  #[structopt(
+    short,
+    long,
+    use_delimiter = true,
+    default_value = "1,10,100",
+    parse(try_from_str = parse_batch_size)
+  )]
+  batch_sizes: Vec<usize>,
+
+  /// Storage types to run the workloads with
+  #[structopt(long, use_delimiter = true, default_value = "volatile,persistent")]
+  storage: Vec<Storage>,
+
+  /// Deduplication settings to run the workloads with (every 10th message is a duplicate when on)
+  #[structopt(
+    long,
+    use_delimiter = true,
+    default_value = "off,on",
+    possible_values = &["off", "on"]
+  )]
+  dedup: Vec<String>,
+
+  /// Size of the string payload of each message, in bytes
+  #[structopt(long, default_value = "100")]
+  payload_size: usize,
+
+  /// Requeue & deduplication time of the benchmark queues, in seconds
+  /// Every unacknowledged message & deduplication ID holds a timer thread for that long
+  #[structopt(long, default_value = "2", parse(try_from_str = parse_lease_time))]
+  lease_time: u32,
+
+  /// Acknowledge messages when dequeuing them instead of one by one
+  #[structopt(long)]
+  auto_ack: bool,
+
+  /// Folder for the persistent queues (default: a temporary folder, removed afterwards)
+  #[structopt(short, long)]
+  data_folder: Option<String>,
+
+  /// Print the reports as JSON lines
+  #[structopt(long)]
+  json: bool,
+}
+
+fn parse_batch_size(value: &str) -> Result<usize, String> {
+  match value.parse::<usize>() {
+    Ok(batch_size) if batch_size > 0 => Ok(batch_size),
+    _ => Err(format!("Invalid batch size: {}", value)),
+  }
+}
+
+// Without a lease time, unacknowledged messages are never requeued & deduplication IDs are kept forever
+fn parse_lease_time(value: &str) -> Result<u32, String> {
+  match value.parse::<u32>() {
+    Ok(lease_time) if lease_time > 0 => Ok(lease_time),
+    _ => Err(format!("Invalid lease time: {}", value)),
+  }
+}
+
+fn main() {
+  let cli = Cli::from_args();
+  if cli.producers == 0 || cli.consumers == 0 {
+    eprintln!("At least one producer & consumer is required");
+    process::exit(1);
+  }
+  if cfg!(debug_assertions) {
+    eprintln!("Warning: built without optimizations, use --release for representative numbers");
+  }
+
+  let (data_folder, is_temporary) = match &cli.data_folder {
+    Some(data_folder) => (PathBuf::from(data_folder), false),
+    None => (
+      std::env::temp_dir().join(format!("corinth-bench-{}", process::id())),
+      true,
+    ),
+  };
+  create_dir_all(&data_folder).expect("Cannot create data folder");
+  let broker = Broker::new(BrokerOptions {
+    data_folder: data_folder.to_string_lossy().into_owned(),
+    // Compacting in between would distort the numbers, it is measured separately
+    compact_interval: 0,
+    ..BrokerOptions::default()
+  });
+  broker.create_queues_folder();
+
+  let mut workloads = Vec::new();
+  for storage in &cli.storage {
+    for dedup in &cli.dedup {
+      for batch_size in &cli.batch_sizes {
+        workloads.push(Arc::new(Workload {
+          storage: *storage,
+          dedup: dedup == "on",
+          batch_size: *batch_size,
+          messages: cli.messages,
+          producers: cli.producers,
+          consumers: cli.consumers,
+          payload_size: cli.payload_size,
+          lease_time: cli.lease_time,
+          auto_ack: cli.auto_ack,
+        }));
+      }
+    }
+  }
+
+  let mut reports = Vec::new();
+  for (index, workload) in workloads.into_iter().enumerate() {
+    if index > 0 {
+      // Let the timers of the previous workload run out, so they don't compete with this one
+      thread::sleep(Duration::from_secs(u64::from(cli.lease_time)) + Duration::from_millis(100));
+    }
+    eprintln!("Running {}...", workload.name());
+    let report = workload::run(&broker, workload);
+    if cli.json {
+      println!(
+        "{}",
+        serde_json::to_string(&report).expect("JSON stringify failed")
+      );
+    }
+    reports.push(report);
+  }
+  if !cli.json {
+    print_table(&reports);
+  }
+
+  if is_temporary {
+    remove_dir_all(&data_folder).ok();
+  }
+}
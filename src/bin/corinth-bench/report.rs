@@ -0,0 +1,119 @@
+use serde_derive::Serialize;
+use std::time::Duration;
+
+// Latencies of one operation, in microseconds
+#[derive(Serialize, Default)]
+pub struct LatencyStats {
+  pub count: usize,
+  pub p50: f64,
+  pub p99: f64,
+  pub max: f64,
+}
+
+fn micros(duration: Duration) -> f64 {
+  duration.as_secs_f64() * 1_000_000.0
+}
+
+impl LatencyStats {
+  pub fn from_samples(samples: &mut [Duration]) -> LatencyStats {
+    if samples.is_empty() {
+      return LatencyStats::default();
+    }
+    samples.sort_unstable();
+    // Nearest rank
+    let percentile = |p: f64| {
+      let rank = (p * samples.len() as f64).ceil() as usize;
+      micros(samples[rank.max(1) - 1])
+    };
+    LatencyStats {
+      count: samples.len(),
+      p50: percentile(0.5),
+      p99: percentile(0.99),
+      max: micros(samples[samples.len() - 1]),
+    }
+  }
+}
+
+#[derive(Serialize)]
+pub struct Report {
+  pub workload: String,
+  pub storage: &'static str,
+  pub dedup: bool,
+  pub batch_size: usize,
+  pub producers: u64,
+  pub consumers: u64,
+  pub num_enqueued: usize,
+  pub num_deduplicated: usize,
+  // Messages whose lease ran out before they were acknowledged
+  pub num_requeued: usize,
+  pub duration_ms: f64,
+  // Enqueued messages per second, until the last producer finished
+  pub enqueue_throughput: f64,
+  // Acknowledged messages per second
+  pub throughput: f64,
+  // Per call, so per batch for enqueue & dequeue
+  pub enqueue: LatencyStats,
+  pub dequeue: LatencyStats,
+  pub ack: LatencyStats,
+  // Compacting an item file with half of its messages deleted, persistent queues only
+  pub compact_ms: Option<f64>,
+}
+
+fn latency_cell(stats: &LatencyStats) -> String {
+  if stats.count == 0 {
+    return String::from("-");
+  }
+  format!("{:.0} / {:.0}", stats.p50, stats.p99)
+}
+
+// Prints the reports with aligned columns
+pub fn print_table(reports: &[Report]) {
+  let headers = [
+    "WORKLOAD",
+    "ENQUEUED",
+    "DEDUPLICATED",
+    "ENQUEUE MSG/S",
+    "MSG/S",
+    "ENQUEUE P50/P99 µs",
+    "DEQUEUE P50/P99 µs",
+    "ACK P50/P99 µs",
+    "COMPACT ms",
+  ];
+  let rows: Vec<Vec<String>> = reports
+    .iter()
+    .map(|report| {
+      vec![
+        report.workload.clone(),
+        report.num_enqueued.to_string(),
+        report.num_deduplicated.to_string(),
+        format!("{:.0}", report.enqueue_throughput),
+        format!("{:.0}", report.throughput),
+        latency_cell(&report.enqueue),
+        latency_cell(&report.dequeue),
+        latency_cell(&report.ack),
+        report
+          .compact_ms
+          .map_or_else(|| String::from("-"), |ms| format!("{:.1}", ms)),
+      ]
+    })
+    .collect();
+
+  let mut widths: Vec<usize> = headers
+    .iter()
+    .map(|header| header.chars().count())
+    .collect();
+  for row in &rows {
+    for (width, cell) in widths.iter_mut().zip(row) {
+      *width = (*width).max(cell.chars().count());
+    }
+  }
+  let header_row: Vec<String> = headers.iter().map(|header| header.to_string()).collect();
+  for row in std::iter::once(&header_row).chain(&rows) {
+    let cells: Vec<String> = row
+      .iter()
+      .zip(&widths)
+      .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+      .collect();
+    println!("{}", cells.join("  ").trim_end());
+  }
+}
@@ -0,0 +1,268 @@
+use corinth::{Broker, BrokerError, NewMessage, QueueSettings};
+use serde_json::json;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::report::{LatencyStats, Report};
+
+// With deduplication, every nth message repeats the deduplication ID of the previous one
+const DUPLICATE_EVERY: u64 = 10;
+
+// Consumers finding the queue empty back off between these waits
+const MIN_IDLE_WAIT: Duration = Duration::from_micros(50);
+const MAX_IDLE_WAIT: Duration = Duration::from_millis(10);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Storage {
+  Volatile,
+  Persistent,
+}
+
+impl Storage {
+  pub fn name(self) -> &'static str {
+    match self {
+      Storage::Volatile => "volatile",
+      Storage::Persistent => "persistent",
+    }
+  }
+}
+
+pub struct Workload {
+  pub storage: Storage,
+  pub dedup: bool,
+  pub batch_size: usize,
+  pub messages: u64,
+  pub producers: u64,
+  pub consumers: u64,
+  pub payload_size: usize,
+  // Seconds, also the lifetime of the deduplication IDs
+  pub lease_time: u32,
+  pub auto_ack: bool,
+}
+
+impl Workload {
+  pub fn name(&self) -> String {
+    format!(
+      "{}-{}-{}",
+      self.storage.name(),
+      if self.dedup { "dedup" } else { "nodedup" },
+      self.batch_size
+    )
+  }
+
+  fn settings(&self) -> QueueSettings {
+    QueueSettings {
+      requeue_time: f64::from(self.lease_time),
+      deduplication_time: self.lease_time,
+      persistent: self.storage == Storage::Persistent,
+      ..QueueSettings::default()
+    }
+  }
+
+  fn message(&self, producer: u64, n: u64) -> NewMessage {
+    let item = json!({
+      "producer": producer,
+      "n": n,
+      "payload": "x".repeat(self.payload_size),
+    });
+    if !self.dedup {
+      return NewMessage::new(item);
+    }
+    let id = if n > 0 && n.is_multiple_of(DUPLICATE_EVERY) {
+      n - 1
+    } else {
+      n
+    };
+    NewMessage::deduplicated(item, &format!("{}-{}", producer, id))
+  }
+}
+
+// Latencies collected by all threads of one operation
+#[derive(Default)]
+struct Samples(Mutex<Vec<Duration>>);
+
+impl Samples {
+  fn add(&self, samples: Vec<Duration>) {
+    self.0.lock().unwrap().extend(samples);
+  }
+
+  fn stats(&self) -> LatencyStats {
+    LatencyStats::from_samples(&mut self.0.lock().unwrap())
+  }
+}
+
+fn timed<T>(samples: &mut Vec<Duration>, operation: impl FnOnce() -> T) -> T {
+  let start = Instant::now();
+  let result = operation();
+  samples.push(start.elapsed());
+  result
+}
+
+// Runs producers & consumers on the same queue at the same time
+// Consumers stop once every enqueued message has been acknowledged
+pub fn run(broker: &Broker, workload: Arc<Workload>) -> Report {
+  let queue = workload.name();
+  broker
+    .create_queue(&queue, workload.settings())
+    .expect("Cannot create benchmark queue");
+
+  let enqueue_samples = Arc::new(Samples::default());
+  let dequeue_samples = Arc::new(Samples::default());
+  let ack_samples = Arc::new(Samples::default());
+  let num_enqueued = Arc::new(AtomicUsize::new(0));
+  let num_deduplicated = Arc::new(AtomicUsize::new(0));
+  let num_acked = Arc::new(AtomicUsize::new(0));
+  let num_requeued = Arc::new(AtomicUsize::new(0));
+  let producers_done = Arc::new(AtomicBool::new(false));
+
+  let start = Instant::now();
+  let producers: Vec<_> = (0..workload.producers)
+    .map(|producer| {
+      let broker = broker.clone();
+      let workload = workload.clone();
+      let queue = queue.clone();
+      let enqueue_samples = enqueue_samples.clone();
+      let num_enqueued = num_enqueued.clone();
+      let num_deduplicated = num_deduplicated.clone();
+      thread::spawn(move || {
+        // The first producers take the remainder
+        let mut num_messages = workload.messages / workload.producers;
+        if producer < workload.messages % workload.producers {
+          num_messages += 1;
+        }
+        let mut samples = Vec::new();
+        let mut n = 0;
+        while n < num_messages {
+          let batch_end = num_messages.min(n + workload.batch_size as u64);
+          let batch = (n..batch_end)
+            .map(|n| workload.message(producer, n))
+            .collect();
+          let result =
+            timed(&mut samples, || broker.enqueue(&queue, batch)).expect("Enqueue failed");
+          num_enqueued.fetch_add(result.enqueued.len(), Ordering::SeqCst);
+          num_deduplicated.fetch_add(result.num_deduplicated, Ordering::SeqCst);
+          n = batch_end;
+        }
+        enqueue_samples.add(samples);
+      })
+    })
+    .collect();
+
+  let consumers: Vec<_> = (0..workload.consumers)
+    .map(|_| {
+      let broker = broker.clone();
+      let workload = workload.clone();
+      let queue = queue.clone();
+      let dequeue_samples = dequeue_samples.clone();
+      let ack_samples = ack_samples.clone();
+      let num_enqueued = num_enqueued.clone();
+      let num_acked = num_acked.clone();
+      let num_requeued = num_requeued.clone();
+      let producers_done = producers_done.clone();
+      thread::spawn(move || {
+        let mut dequeue_latencies = Vec::new();
+        let mut ack_latencies = Vec::new();
+        let mut idle_wait = MIN_IDLE_WAIT;
+        loop {
+          let messages = timed(&mut dequeue_latencies, || {
+            broker.dequeue(&queue, workload.batch_size, workload.auto_ack)
+          })
+          .expect("Dequeue failed");
+          if messages.is_empty() {
+            // Read the flag first, all messages are counted once it is set
+            let done = producers_done.load(Ordering::SeqCst);
+            if done && num_acked.load(Ordering::SeqCst) >= num_enqueued.load(Ordering::SeqCst) {
+              break;
+            }
+            // Don't keep the queue locked for the producers & the other consumers
+            thread::sleep(idle_wait);
+            idle_wait = (idle_wait * 2).min(MAX_IDLE_WAIT);
+            continue;
+          }
+          idle_wait = MIN_IDLE_WAIT;
+          if workload.auto_ack {
+            num_acked.fetch_add(messages.len(), Ordering::SeqCst);
+            continue;
+          }
+          for message in messages {
+            match timed(&mut ack_latencies, || broker.ack(&queue, message.id())) {
+              Ok(()) => {
+                num_acked.fetch_add(1, Ordering::SeqCst);
+              }
+              // The lease ran out, it is delivered again
+              Err(BrokerError::MessageNotFound) => {
+                num_requeued.fetch_add(1, Ordering::SeqCst);
+              }
+              Err(error) => panic!("Ack failed: {}", error),
+            }
+          }
+        }
+        dequeue_samples.add(dequeue_latencies);
+        ack_samples.add(ack_latencies);
+      })
+    })
+    .collect();
+
+  for producer in producers {
+    producer.join().expect("Producer failed");
+  }
+  let produce_duration = start.elapsed();
+  producers_done.store(true, Ordering::SeqCst);
+  for consumer in consumers {
+    consumer.join().expect("Consumer failed");
+  }
+  let duration = start.elapsed();
+
+  let compact_ms = match workload.storage {
+    Storage::Persistent => Some(measure_compaction(broker, &queue, &workload)),
+    Storage::Volatile => None,
+  };
+  broker
+    .delete_queue(&queue)
+    .expect("Cannot delete benchmark queue");
+
+  let num_enqueued = num_enqueued.load(Ordering::SeqCst);
+  Report {
+    workload: workload.name(),
+    storage: workload.storage.name(),
+    dedup: workload.dedup,
+    batch_size: workload.batch_size,
+    producers: workload.producers,
+    consumers: workload.consumers,
+    num_enqueued,
+    num_deduplicated: num_deduplicated.load(Ordering::SeqCst),
+    num_requeued: num_requeued.load(Ordering::SeqCst),
+    duration_ms: duration.as_secs_f64() * 1000.0,
+    enqueue_throughput: num_enqueued as f64 / produce_duration.as_secs_f64(),
+    throughput: num_enqueued as f64 / duration.as_secs_f64(),
+    enqueue: enqueue_samples.stats(),
+    dequeue: dequeue_samples.stats(),
+    ack: ack_samples.stats(),
+    compact_ms,
+  }
+}
+
+// Fills the queue with the workload's messages, removes half of them
+// & measures rewriting the item file with the remaining half
+fn measure_compaction(broker: &Broker, queue: &str, workload: &Workload) -> f64 {
+  let mut n = 0;
+  while n < workload.messages {
+    let batch_end = workload.messages.min(n + 1000);
+    let batch = (n..batch_end)
+      .map(|n| NewMessage::new(json!({ "n": n, "payload": "x".repeat(workload.payload_size) })))
+      .collect();
+    broker.enqueue(queue, batch).expect("Enqueue failed");
+    n = batch_end;
+  }
+  broker
+    .dequeue(queue, (workload.messages / 2) as usize, true)
+    .expect("Dequeue failed");
+
+  let mut queue_map = broker.queues();
+  let queue = queue_map.get_mut(queue).unwrap();
+  let start = Instant::now();
+  queue.compact();
+  start.elapsed().as_secs_f64() * 1000.0
+}
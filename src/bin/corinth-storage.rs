@@ -0,0 +1,173 @@
+// Offline inspection & repair of the queues in a data folder, while the server is stopped
+use corinth::queue::unwrap_message;
+use corinth::storage::{
+  compact_queue, inspect_queue, read_queue_messages, repair_queue, stored_queues, QueueReport,
+  StorageError,
+};
+use std::process::exit;
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+#[structopt(
+  name = "corinth-storage",
+  about = "Inspects & repairs stored queues, compact & repair refuse to run while the server uses the data folder"
+)]
+struct Cli {
+  /// Data folder of the server (storage.data_folder in the config file)
+  #[structopt(short, long, env = "CORINTH_BASE_FOLDER", default_value = ".corinth")]
+  data_folder: String,
+
+  #[structopt(subcommand)]
+  command: Command,
+}
+
+#[derive(StructOpt)]
+enum Command {
+  /// Validates metadata & item files, fails if problems are found
+  Check {
+    /// Queues to check (default: all)
+    queues: Vec<String>,
+    /// Print the reports as JSON
+    #[structopt(long)]
+    json: bool,
+  },
+
+  /// Prints the messages of a queue as JSON Lines, in the format of the export route
+  Dump { queue: String },
+
+  /// Removes deleted records from item files
+  Compact {
+    /// Queues to compact (default: all)
+    queues: Vec<String>,
+  },
+
+  /// Truncates the item file at the first corrupt record & restores unreadable metadata
+  Repair {
+    queue: String,
+    /// Also drop valid records following the corruption & recreate missing metadata with default settings
+    #[structopt(long)]
+    force: bool,
+  },
+}
+
+fn queue_names(data_folder: &str, queues: Vec<String>) -> Result<Vec<String>, StorageError> {
+  if queues.is_empty() {
+    stored_queues(data_folder)
+  } else {
+    Ok(queues)
+  }
+}
+
+fn print_report(report: &QueueReport) {
+  let items = &report.items;
+  let status = if report.is_healthy() {
+    "ok"
+  } else {
+    "PROBLEMS"
+  };
+  println!(
+    "{}: {} ({} live, {} deleted, {} corrupt records, {} bytes)",
+    report.name, status, items.num_live, items.num_deleted, items.num_corrupt, items.size
+  );
+  for problem in &report.meta.problems {
+    println!("  metadata: {}", problem);
+  }
+  if let Some(corrupt) = &items.first_corrupt {
+    println!(
+      "  items.jsonl line {} (byte {}): {}",
+      corrupt.line, corrupt.offset, corrupt.reason
+    );
+    if items.num_valid_after_corrupt > 0 {
+      println!(
+        "  {} valid records follow it, repairing requires --force",
+        items.num_valid_after_corrupt
+      );
+    }
+  }
+  if items.num_orphan_deletions > 0 {
+    println!(
+      "  {} deletion records without a message to delete",
+      items.num_orphan_deletions
+    );
+  }
+}
+
+// Returns whether all queues are healthy
+fn check(data_folder: &str, queues: Vec<String>, json: bool) -> Result<bool, StorageError> {
+  let mut reports = Vec::new();
+  for name in queue_names(data_folder, queues)? {
+    reports.push(inspect_queue(data_folder, &name)?);
+  }
+  if json {
+    println!(
+      "{}",
+      serde_json::to_string_pretty(&reports).expect("JSON stringify failed")
+    );
+  } else {
+    reports.iter().for_each(print_report);
+  }
+  Ok(reports.iter().all(QueueReport::is_healthy))
+}
+
+fn run(cli: Cli) -> Result<bool, StorageError> {
+  let data_folder = &cli.data_folder;
+  match cli.command {
+    Command::Check { queues, json } => return check(data_folder, queues, json),
+    Command::Dump { queue } => {
+      for message in read_queue_messages(data_folder, &queue)? {
+        let line = serde_json::to_string(&unwrap_message(message)).expect("JSON stringify failed");
+        println!("{}", line);
+      }
+    }
+    Command::Compact { queues } => {
+      for name in queue_names(data_folder, queues)? {
+        match compact_queue(data_folder, &name) {
+          Ok(num_messages) => println!("{}: compacted ({} messages)", name, num_messages),
+          Err(StorageError::CorruptRecords) => {
+            eprintln!(
+              "{}: {}, run repair first",
+              name,
+              StorageError::CorruptRecords
+            );
+            return Ok(false);
+          }
+          Err(error) => {
+            eprintln!("{}: {}", name, error);
+            return Ok(false);
+          }
+        }
+      }
+    }
+    Command::Repair { queue, force } => {
+      let report = repair_queue(data_folder, &queue, force)?;
+      if let Some(tail_file) = &report.tail_file {
+        println!(
+          "Truncated {} bytes, saved to {}",
+          report.num_truncated_bytes, tail_file
+        );
+      }
+      if report.restored_meta_from_backup {
+        println!("Restored meta.json from its backup");
+      }
+      if report.recreated_meta {
+        println!("Recreated meta.json with default settings");
+      }
+      if report.tail_file.is_none() && !report.restored_meta_from_backup && !report.recreated_meta {
+        println!("Nothing to repair");
+      }
+    }
+  }
+  Ok(true)
+}
+
+fn main() {
+  let cli = Cli::from_args();
+  match run(cli) {
+    Ok(true) => {}
+    Ok(false) => exit(1),
+    Err(error) => {
+      eprintln!("Error: {}", error);
+      exit(1);
+    }
+  }
+}
@@ -0,0 +1,451 @@
+// Command-line client for Corinth servers
+mod output;
+
+use actix_web::rt::time::delay_for;
+use corinth_client::api::EnqueueResult;
+use corinth_client::{
+  Client, ClientError, CreateQueueOptions, Message, NewMessage, QueuePatchBody,
+};
+use output::{print_json, print_message_line, print_messages, print_queues, Format};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{stdin, BufRead, BufReader};
+use std::path::PathBuf;
+use std::process::exit;
+use std::time::Duration;
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+#[structopt(name = "corinthctl", about = "Command-line client for Corinth servers")]
+struct Cli {
+  /// Server URL
+  #[structopt(
+    short,
+    long,
+    env = "CORINTH_URL",
+    default_value = "http://localhost:44444"
+  )]
+  url: String,
+
+  /// API key, sent as bearer token
+  #[structopt(long, env = "CORINTH_API_KEY", hide_env_values = true)]
+  api_key: Option<String>,
+
+  /// Output format: table or json
+  #[structopt(short, long, default_value = "table")]
+  output: Format,
+
+  #[structopt(subcommand)]
+  command: Command,
+}
+
+#[derive(StructOpt)]
+enum Command {
+  /// Lists all queues
+  List,
+
+  /// Creates a queue, unset settings use the server's defaults
+  Create {
+    queue: String,
+    /// Time until unacknowledged messages are requeued (in seconds, may be fractional)
+    #[structopt(long)]
+    requeue_time: Option<f64>,
+    /// Time deduplication IDs are tracked for (in seconds)
+    #[structopt(long)]
+    deduplication_time: Option<u32>,
+    /// Keep the queue in memory only
+    #[structopt(long)]
+    volatile: bool,
+    /// Maximum amount of messages (0 for unlimited)
+    #[structopt(long)]
+    max_length: Option<u64>,
+    /// Queue messages are moved to after too many requeues
+    #[structopt(long)]
+    dead_letter_queue: Option<String>,
+    /// Requeues after which messages are moved to the dead letter queue
+    #[structopt(long, requires = "dead-letter-queue")]
+    dead_letter_threshold: Option<u16>,
+  },
+
+  /// Changes settings of a queue
+  Edit {
+    queue: String,
+    /// Time until unacknowledged messages are requeued (in seconds, may be fractional)
+    #[structopt(long)]
+    requeue_time: Option<f64>,
+    /// Time deduplication IDs are tracked for (in seconds)
+    #[structopt(long)]
+    deduplication_time: Option<u32>,
+    /// Maximum amount of messages (0 for unlimited)
+    #[structopt(long)]
+    max_length: Option<u64>,
+  },
+
+  /// Deletes a queue & its messages
+  Delete { queue: String },
+
+  /// Removes all messages of a queue
+  Purge { queue: String },
+
+  /// Compacts the file of a persistent queue
+  Compact { queue: String },
+
+  /// Enqueues messages, one JSON object per line, read from a file or stdin
+  Enqueue {
+    queue: String,
+    /// File to read instead of stdin
+    #[structopt(short, long, parse(from_os_str))]
+    file: Option<PathBuf>,
+    /// Field of the messages used as deduplication ID
+    #[structopt(long)]
+    deduplication_field: Option<String>,
+    /// Messages sent per request
+    #[structopt(long, default_value = "100")]
+    batch_size: usize,
+  },
+
+  /// Dequeues messages
+  Dequeue {
+    queue: String,
+    /// Amount of messages to dequeue
+    #[structopt(short = "n", long, default_value = "1")]
+    amount: u8,
+    /// Acknowledge the messages right away
+    #[structopt(long)]
+    ack: bool,
+  },
+
+  /// Shows the head of a queue without dequeuing it
+  Peek { queue: String },
+
+  /// Acknowledges dequeued messages
+  Ack {
+    queue: String,
+    #[structopt(required = true)]
+    message_ids: Vec<String>,
+  },
+
+  /// Moves the messages of a queue's dead letter queue back into it (as new messages)
+  Redrive {
+    queue: String,
+    /// Maximum amount of messages to move
+    #[structopt(long)]
+    max: Option<usize>,
+  },
+
+  /// Prints the last messages of a queue & follows newly queued ones, without dequeuing them
+  ///
+  /// Needs an admin key (uses the export route). Messages that are dequeued before the next poll
+  /// are not printed
+  Tail {
+    queue: String,
+    /// Amount of queued messages to print first
+    #[structopt(short = "n", long, default_value = "10")]
+    lines: usize,
+    /// Time between polls (in seconds)
+    #[structopt(long, default_value = "1")]
+    interval: f64,
+  },
+}
+
+#[derive(Debug)]
+enum CtlError {
+  Client(ClientError),
+  Input(String),
+}
+
+impl From<ClientError> for CtlError {
+  fn from(error: ClientError) -> Self {
+    CtlError::Client(error)
+  }
+}
+
+impl std::fmt::Display for CtlError {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    match self {
+      CtlError::Client(error) => write!(f, "{}", error),
+      CtlError::Input(message) => write!(f, "{}", message),
+    }
+  }
+}
+
+// Prints a confirmation, unless the output is meant for scripts
+fn done(format: Format, message: String) {
+  if format == Format::Table {
+    println!("{}", message);
+  }
+}
+
+// Reads one message per line, blank lines are skipped
+fn read_messages(
+  file: Option<PathBuf>,
+  deduplication_field: Option<&str>,
+) -> Result<Vec<NewMessage>, CtlError> {
+  let reader: Box<dyn BufRead> = match file {
+    Some(path) => {
+      let file = File::open(&path)
+        .map_err(|error| CtlError::Input(format!("Cannot open {}: {}", path.display(), error)))?;
+      Box::new(BufReader::new(file))
+    }
+    None => Box::new(BufReader::new(stdin())),
+  };
+
+  let mut messages = Vec::new();
+  for (index, line) in reader.lines().enumerate() {
+    let line = line.map_err(|error| CtlError::Input(error.to_string()))?;
+    if line.trim().is_empty() {
+      continue;
+    }
+    let item: Value = serde_json::from_str(&line)
+      .ok()
+      .filter(Value::is_object)
+      .ok_or_else(|| CtlError::Input(format!("Line {} is not a JSON object", index + 1)))?;
+    let deduplication_id = deduplication_field
+      .and_then(|field| item.get(field))
+      .map(|value| match value {
+        Value::String(id) => id.clone(),
+        value => value.to_string(),
+      });
+    messages.push(NewMessage {
+      item,
+      deduplication_id,
+    });
+  }
+  Ok(messages)
+}
+
+async fn enqueue(
+  client: &Client,
+  format: Format,
+  queue: &str,
+  messages: Vec<NewMessage>,
+  batch_size: usize,
+) -> Result<(), CtlError> {
+  let mut total = EnqueueResult {
+    num_enqueued: 0,
+    num_deduplicated: 0,
+    items: Vec::new(),
+  };
+  for batch in messages.chunks(batch_size.max(1)) {
+    let result = client.enqueue(queue, batch.to_vec()).await?;
+    total.num_enqueued += result.num_enqueued;
+    total.num_deduplicated += result.num_deduplicated;
+    total.items.extend(result.items);
+  }
+
+  if format == Format::Json {
+    print_json(&total);
+  } else {
+    println!(
+      "Enqueued {} messages ({} deduplicated)",
+      total.num_enqueued, total.num_deduplicated
+    );
+  }
+  Ok(())
+}
+
+// Dequeues from the dead letter queue, enqueues into the queue, then acknowledges
+// If enqueueing fails, the dequeued messages are requeued into the dead letter queue
+async fn redrive(
+  client: &Client,
+  format: Format,
+  queue: &str,
+  max: Option<usize>,
+) -> Result<(), CtlError> {
+  let dead_letter_queue = client
+    .queue(queue)
+    .await?
+    .dead_letter
+    .ok_or_else(|| CtlError::Input(format!("Queue '{}' has no dead letter queue", queue)))?
+    .name;
+
+  // Messages failing again while redriving must not be moved over & over
+  let size = client.queue(&dead_letter_queue).await?.size;
+  let mut remaining = max.map_or(size, |max| max.min(size));
+  let mut num_moved = 0;
+  while remaining > 0 {
+    let amount = remaining.min(usize::from(u8::MAX)) as u8;
+    let messages = client.dequeue(&dead_letter_queue, amount, false).await?;
+    if messages.is_empty() {
+      break;
+    }
+    let new_messages = messages
+      .iter()
+      .map(|message| NewMessage::new(message.item.clone()))
+      .collect();
+    client.enqueue(queue, new_messages).await?;
+    for message in &messages {
+      client.ack(&dead_letter_queue, &message.id).await?;
+    }
+    num_moved += messages.len();
+    remaining -= messages.len();
+  }
+
+  if format == Format::Json {
+    print_json(&serde_json::json!({ "num_moved": num_moved }));
+  } else {
+    println!(
+      "Moved {} messages from '{}' to '{}'",
+      num_moved, dead_letter_queue, queue
+    );
+  }
+  Ok(())
+}
+
+// Polls the export route for messages queued since the newest one printed
+// Messages enqueued & dequeued between two polls are never printed
+async fn tail(
+  client: &Client,
+  format: Format,
+  queue: &str,
+  lines: usize,
+  interval: Duration,
+) -> Result<(), CtlError> {
+  let messages = client.export(queue, false).await?;
+  for message in messages.iter().skip(messages.len().saturating_sub(lines)) {
+    print_message_line(format, message);
+  }
+  // Messages queued in the same millisecond as the newest one are exported again
+  let mut queued_since = 0;
+  let mut seen: HashSet<String> = HashSet::new();
+  for message in messages {
+    if message.queued_at > queued_since {
+      queued_since = message.queued_at;
+      seen.clear();
+    }
+    if message.queued_at == queued_since {
+      seen.insert(message.id);
+    }
+  }
+
+  loop {
+    delay_for(interval).await;
+    let messages: Vec<Message> = client.export_since(queue, queued_since).await?;
+    for message in messages {
+      if seen.contains(&message.id) {
+        continue;
+      }
+      print_message_line(format, &message);
+      if message.queued_at > queued_since {
+        queued_since = message.queued_at;
+        seen.clear();
+      }
+      if message.queued_at == queued_since {
+        seen.insert(message.id);
+      }
+    }
+  }
+}
+
+async fn run(cli: Cli) -> Result<(), CtlError> {
+  let mut client = Client::new(&cli.url);
+  if let Some(api_key) = &cli.api_key {
+    client = client.api_key(api_key);
+  }
+  let format = cli.output;
+
+  match cli.command {
+    Command::List => print_queues(format, &client.list_queues().await?),
+    Command::Create {
+      queue,
+      requeue_time,
+      deduplication_time,
+      volatile,
+      max_length,
+      dead_letter_queue,
+      dead_letter_threshold,
+    } => {
+      let options = CreateQueueOptions {
+        requeue_time,
+        deduplication_time,
+        persistent: if volatile { Some(false) } else { None },
+        max_length,
+        dead_letter_queue_name: dead_letter_queue,
+        dead_letter_queue_threshold: dead_letter_threshold,
+      };
+      client.create_queue(&queue, &options).await?;
+      done(format, format!("Queue '{}' created", queue));
+    }
+    Command::Edit {
+      queue,
+      requeue_time,
+      deduplication_time,
+      max_length,
+    } => {
+      let patch = QueuePatchBody {
+        requeue_time,
+        deduplication_time,
+        max_length,
+      };
+      client.edit_queue(&queue, &patch).await?;
+      done(format, format!("Queue '{}' edited", queue));
+    }
+    Command::Delete { queue } => {
+      client.delete_queue(&queue).await?;
+      done(format, format!("Queue '{}' deleted", queue));
+    }
+    Command::Purge { queue } => {
+      client.purge_queue(&queue).await?;
+      done(format, format!("Queue '{}' purged", queue));
+    }
+    Command::Compact { queue } => {
+      client.compact_queue(&queue).await?;
+      done(format, format!("Queue '{}' compacted", queue));
+    }
+    Command::Enqueue {
+      queue,
+      file,
+      deduplication_field,
+      batch_size,
+    } => {
+      let messages = read_messages(file, deduplication_field.as_deref())?;
+      enqueue(&client, format, &queue, messages, batch_size).await?;
+    }
+    Command::Dequeue { queue, amount, ack } => {
+      let messages = client.dequeue(&queue, amount, ack).await?;
+      print_messages(format, &messages);
+    }
+    Command::Peek { queue } => {
+      let messages: Vec<Message> = client.peek(&queue).await?.into_iter().collect();
+      print_messages(format, &messages);
+    }
+    Command::Ack { queue, message_ids } => {
+      for message_id in &message_ids {
+        client.ack(&queue, message_id).await?;
+      }
+      done(
+        format,
+        format!("Acknowledged {} messages", message_ids.len()),
+      );
+    }
+    Command::Redrive { queue, max } => redrive(&client, format, &queue, max).await?,
+    Command::Tail {
+      queue,
+      lines,
+      interval,
+    } => {
+      if !interval.is_finite() || interval <= 0.0 {
+        return Err(CtlError::Input(String::from("Invalid interval")));
+      }
+      tail(
+        &client,
+        format,
+        &queue,
+        lines,
+        Duration::from_secs_f64(interval),
+      )
+      .await?;
+    }
+  }
+  Ok(())
+}
+
+#[actix_web::main]
+async fn main() {
+  let cli = Cli::from_args();
+  if let Err(error) = run(cli).await {
+    eprintln!("Error: {}", error);
+    exit(1);
+  }
+}
@@ -0,0 +1,127 @@
+use chrono::{DateTime, SecondsFormat};
+use corinth_client::{Message, QueueInfo};
+use serde::Serialize;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Format {
+  Table,
+  Json,
+}
+
+impl FromStr for Format {
+  type Err = String;
+
+  fn from_str(value: &str) -> Result<Self, Self::Err> {
+    match value {
+      "table" => Ok(Format::Table),
+      "json" => Ok(Format::Json),
+      _ => Err(format!("Invalid output format: {}", value)),
+    }
+  }
+}
+
+pub fn print_json<T: Serialize>(value: &T) {
+  println!(
+    "{}",
+    serde_json::to_string_pretty(value).expect("JSON stringify failed")
+  );
+}
+
+// Prints the rows with aligned columns
+pub fn print_table(headers: &[&str], rows: &[Vec<String>]) {
+  let mut widths: Vec<usize> = headers.iter().map(|header| header.len()).collect();
+  for row in rows {
+    for (width, cell) in widths.iter_mut().zip(row) {
+      *width = (*width).max(cell.chars().count());
+    }
+  }
+
+  let header_row: Vec<String> = headers.iter().map(|header| header.to_string()).collect();
+  for row in std::iter::once(&header_row).chain(rows) {
+    let cells: Vec<String> = row
+      .iter()
+      .zip(&widths)
+      .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+      .collect();
+    println!("{}", cells.join("  ").trim_end());
+  }
+}
+
+// Unix milliseconds as RFC 3339 (UTC)
+fn format_time(timestamp: u64) -> String {
+  DateTime::from_timestamp_millis(timestamp as i64).map_or_else(
+    || timestamp.to_string(),
+    |time| time.to_rfc3339_opts(SecondsFormat::Millis, true),
+  )
+}
+
+pub fn print_queues(format: Format, queues: &[QueueInfo]) {
+  if format == Format::Json {
+    return print_json(&queues);
+  }
+  let rows: Vec<Vec<String>> = queues
+    .iter()
+    .map(|queue| {
+      vec![
+        queue.name.clone(),
+        queue.size.to_string(),
+        queue.num_unacknowledged.to_string(),
+        queue.num_acknowledged.to_string(),
+        queue.num_requeued.to_string(),
+        queue.requeue_time.to_string(),
+        queue.persistent.to_string(),
+        queue
+          .dead_letter
+          .as_ref()
+          .map_or(String::from("-"), |dead_letter| {
+            format!("{} ({})", dead_letter.name, dead_letter.threshold)
+          }),
+      ]
+    })
+    .collect();
+  print_table(
+    &[
+      "NAME",
+      "SIZE",
+      "UNACKED",
+      "ACKED",
+      "REQUEUED",
+      "REQUEUE TIME",
+      "PERSISTENT",
+      "DEAD LETTER",
+    ],
+    &rows,
+  );
+}
+
+fn message_row(message: &Message) -> Vec<String> {
+  vec![
+    message.id.clone(),
+    format_time(message.queued_at),
+    format!("{:?}", message.state),
+    message.num_requeues.to_string(),
+    message.item.to_string(),
+  ]
+}
+
+const MESSAGE_HEADERS: [&str; 5] = ["ID", "QUEUED AT", "STATE", "REQUEUES", "ITEM"];
+
+pub fn print_messages(format: Format, messages: &[Message]) {
+  if format == Format::Json {
+    return print_json(&messages);
+  }
+  let rows: Vec<Vec<String>> = messages.iter().map(message_row).collect();
+  print_table(&MESSAGE_HEADERS, &rows);
+}
+
+// One line per message, for output that is printed as it arrives
+pub fn print_message_line(format: Format, message: &Message) {
+  match format {
+    Format::Json => println!(
+      "{}",
+      serde_json::to_string(message).expect("JSON stringify failed")
+    ),
+    Format::Table => println!("{}", message_row(message).join("  ")),
+  }
+}
@@ -0,0 +1,377 @@
+use crate::fs::{file_exists, write_file_atomic};
+use crate::queue::{
+  get_queue_folder, queue_meta_backup_file, queue_meta_file, Message, Queue, QueueMeta,
+  QueueSettings,
+};
+pub use corinth_api::NewMessage;
+use log::{info, warn};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{create_dir_all, metadata, read_dir, read_to_string, remove_file};
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+pub type QueueMap = HashMap<String, Queue>;
+
+// Storage & background task settings of a broker
+#[derive(Clone)]
+pub struct BrokerOptions {
+  // Folder the persistent queues are stored in
+  pub data_folder: String,
+  // Seconds between compactions of persistent queues, 0 to disable
+  pub compact_interval: u64,
+  // Seconds between metadata flushes of persistent queues, 0 to disable
+  pub meta_flush_interval: u64,
+}
+
+impl Default for BrokerOptions {
+  fn default() -> Self {
+    BrokerOptions {
+      data_folder: String::from(".corinth"),
+      compact_interval: 86400,
+      meta_flush_interval: 5,
+    }
+  }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum BrokerError {
+  QueueNotFound,
+  QueueAlreadyExists,
+  // Messages don't fit into the queue's max length
+  QueueFull,
+  DeadLetterTargetNotFound,
+  // The queue is the dead letter queue of another queue
+  DeadLetterQueueInUse,
+  // Not (or no longer) waiting to be acknowledged
+  MessageNotFound,
+}
+
+impl fmt::Display for BrokerError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let message = match self {
+      BrokerError::QueueNotFound => "Queue not found",
+      BrokerError::QueueAlreadyExists => "Queue already exists",
+      BrokerError::QueueFull => "Queue is full",
+      BrokerError::DeadLetterTargetNotFound => "Dead letter target not found",
+      BrokerError::DeadLetterQueueInUse => "Dead letter queue is in use",
+      BrokerError::MessageNotFound => "Message not found",
+    };
+    write!(f, "{}", message)
+  }
+}
+
+impl std::error::Error for BrokerError {}
+
+pub struct EnqueueResult {
+  pub enqueued: Vec<Message>,
+  pub num_deduplicated: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+struct VolatileQueue {
+  name: String,
+  meta: QueueMeta,
+  items: Vec<Message>,
+}
+
+// Handle to a set of queues stored in one data folder
+// Clones share the queues, separate brokers (with separate data folders) are independent
+#[derive(Clone)]
+pub struct Broker {
+  options: Arc<BrokerOptions>,
+  queues: Arc<Mutex<QueueMap>>,
+}
+
+impl Broker {
+  pub fn new(options: BrokerOptions) -> Broker {
+    Broker {
+      options: Arc::new(options),
+      queues: Arc::new(Mutex::new(HashMap::new())),
+    }
+  }
+
+  pub fn data_folder(&self) -> &str {
+    &self.options.data_folder
+  }
+
+  // Folder the queue's items & metadata are stored in
+  pub fn queue_folder(&self, name: &str) -> String {
+    get_queue_folder(self.data_folder(), name)
+  }
+
+  pub fn create_queues_folder(&self) -> String {
+    let path = Path::new(self.data_folder()).join("queues");
+    create_dir_all(&path).ok();
+    path.into_os_string().into_string().expect("Path error")
+  }
+
+  // Locks the queues, for operations the broker has no method for
+  // Timers lock the queues as well, so don't keep them locked longer than needed
+  pub fn queues(&self) -> MutexGuard<'_, QueueMap> {
+    self.queues.lock().unwrap()
+  }
+
+  pub fn queue_exists(&self, name: &str) -> bool {
+    self.queues().contains_key(name)
+  }
+
+  // Adds a new queue to the locked queues & starts its timers
+  // Neither checks whether the queue exists nor its dead letter target
+  pub fn insert_queue(&self, queue_map: &mut QueueMap, name: &str, settings: QueueSettings) {
+    let queue = Queue::new(
+      String::from(name),
+      self.queue_folder(name),
+      Arc::downgrade(&self.queues),
+      settings,
+    );
+    self.add_queue(queue_map, queue);
+  }
+
+  fn add_queue(&self, queue_map: &mut QueueMap, mut queue: Queue) {
+    queue.start_compact_interval(self.options.compact_interval);
+    queue.start_metadata_flush_interval(self.options.meta_flush_interval);
+    queue_map.insert(queue.get_name(), queue);
+  }
+
+  pub fn create_queue(&self, name: &str, settings: QueueSettings) -> Result<(), BrokerError> {
+    let mut queue_map = self.queues();
+    if queue_map.contains_key(name) {
+      return Err(BrokerError::QueueAlreadyExists);
+    }
+    if let Some(dead_letter_queue) = &settings.dead_letter_queue {
+      if !queue_map.contains_key(&dead_letter_queue.name) {
+        return Err(BrokerError::DeadLetterTargetNotFound);
+      }
+    }
+    self.insert_queue(&mut queue_map, name, settings);
+    Ok(())
+  }
+
+  // Removes the queue & its files
+  pub fn delete_queue(&self, name: &str) -> Result<(), BrokerError> {
+    let mut queue_map = self.queues();
+    if !queue_map.contains_key(name) {
+      return Err(BrokerError::QueueNotFound);
+    }
+    let is_dead_letter_queue = queue_map.values().any(|queue| {
+      queue
+        .dead_letter_queue()
+        .is_some_and(|dead_letter_queue| dead_letter_queue.name == name)
+    });
+    if is_dead_letter_queue {
+      return Err(BrokerError::DeadLetterQueueInUse);
+    }
+    let mut queue = queue_map.remove(name).unwrap();
+    queue.purge(true);
+    Ok(())
+  }
+
+  // Removes all messages of the queue
+  pub fn purge_queue(&self, name: &str) -> Result<(), BrokerError> {
+    let mut queue_map = self.queues();
+    let queue = queue_map.get_mut(name).ok_or(BrokerError::QueueNotFound)?;
+    queue.purge(false);
+    Ok(())
+  }
+
+  // Enqueues all messages or none, if they don't fit into the queue
+  pub fn enqueue(
+    &self,
+    name: &str,
+    messages: Vec<NewMessage>,
+  ) -> Result<EnqueueResult, BrokerError> {
+    let mut queue_map = self.queues();
+    let queue = queue_map.get_mut(name).ok_or(BrokerError::QueueNotFound)?;
+    if !queue.can_fit_messages(messages.len() as u64) {
+      return Err(BrokerError::QueueFull);
+    }
+
+    let mut result = EnqueueResult {
+      enqueued: Vec::new(),
+      num_deduplicated: 0,
+    };
+    for message in messages {
+      match queue.try_enqueue(message.item, message.deduplication_id) {
+        Some(message) => result.enqueued.push(message),
+        None => result.num_deduplicated += 1,
+      }
+    }
+    Ok(result)
+  }
+
+  // Dequeues up to amount messages
+  // Unless auto acknowledged, they are requeued if not acknowledged in time
+  pub fn dequeue(
+    &self,
+    name: &str,
+    amount: usize,
+    auto_ack: bool,
+  ) -> Result<Vec<Message>, BrokerError> {
+    let mut queue_map = self.queues();
+    let queue = queue_map.get_mut(name).ok_or(BrokerError::QueueNotFound)?;
+    let mut messages = Vec::new();
+    while messages.len() < amount {
+      match queue.dequeue(auto_ack) {
+        Some(message) => messages.push(message),
+        None => break,
+      }
+    }
+    Ok(messages)
+  }
+
+  // Returns the queue's head without dequeuing it
+  pub fn peek(&self, name: &str) -> Result<Option<Message>, BrokerError> {
+    let queue_map = self.queues();
+    let queue = queue_map.get(name).ok_or(BrokerError::QueueNotFound)?;
+    Ok(queue.peek())
+  }
+
+  pub fn ack(&self, name: &str, message_id: &str) -> Result<(), BrokerError> {
+    let mut queue_map = self.queues();
+    let queue = queue_map.get_mut(name).ok_or(BrokerError::QueueNotFound)?;
+    if queue.ack(String::from(message_id)) {
+      Ok(())
+    } else {
+      Err(BrokerError::MessageNotFound)
+    }
+  }
+
+  // Postpones requeuing an unacknowledged message, by the queue's requeue time if no lifetime is given
+  // Returns the time (in milliseconds) the message is requeued at now
+  pub fn extend_lease(
+    &self,
+    name: &str,
+    message_id: &str,
+    lifetime: Option<Duration>,
+  ) -> Result<u64, BrokerError> {
+    let mut queue_map = self.queues();
+    let queue = queue_map.get_mut(name).ok_or(BrokerError::QueueNotFound)?;
+    // Requeue times of library callers & stored metadata are not validated, they must not panic here
+    let lifetime = lifetime
+      .unwrap_or_else(|| Duration::try_from_secs_f64(queue.requeue_time()).unwrap_or_default());
+    queue
+      .extend_lease(message_id, lifetime)
+      .ok_or(BrokerError::MessageNotFound)
+  }
+
+  // Reads the persistent queues of the data folder
+  // Replaying the item log can take a while, so the queues are only locked to insert
+  pub fn load(&self) {
+    let folder = self.create_queues_folder();
+    let entries = read_dir(folder).expect("readdir failed");
+
+    for entry in entries {
+      let file = entry.unwrap();
+      let queue_name = file.file_name().into_string().unwrap();
+      if metadata(file.path()).unwrap().is_dir() {
+        let queue_folder = self.queue_folder(&queue_name);
+        if file_exists(&queue_meta_file(&queue_folder))
+          || file_exists(&queue_meta_backup_file(&queue_folder))
+        {
+          let queue = Queue::from_disk(
+            queue_name.clone(),
+            queue_folder,
+            Arc::downgrade(&self.queues),
+          );
+          if let Some(queue) = queue {
+            self.add_queue(&mut self.queues(), queue);
+          } else {
+            warn!("Metadata of {} is corrupted, skipping...", queue_name)
+          }
+        } else {
+          warn!("Metadata file not found, skipping...")
+        }
+      } else {
+        warn!("File in data folder not a folder, skipping...")
+      }
+    }
+  }
+
+  // Writes pending metadata changes & syncs the item files of all persistent queues
+  pub fn flush(&self) {
+    for queue in self.queues().values_mut() {
+      queue.sync_to_disk();
+    }
+  }
+
+  fn volatile_queues_file(&self) -> String {
+    let path = Path::new(self.data_folder()).join("volatile.json");
+    path.into_os_string().into_string().expect("Path error")
+  }
+
+  // Keeps the volatile queues (and optionally their messages) across restarts
+  // Unacknowledged messages are stored in front of the queued ones
+  pub fn save_volatile_queues(&self, include_messages: bool) {
+    let queues: Vec<VolatileQueue> = self
+      .queues()
+      .values()
+      .filter(|queue| !queue.is_persistent())
+      .map(|queue| {
+        let items = if include_messages {
+          queue
+            .unacked_items()
+            .into_iter()
+            .chain(queue.items().iter())
+            .cloned()
+            .collect()
+        } else {
+          Vec::new()
+        };
+        VolatileQueue {
+          name: queue.get_name(),
+          meta: queue.get_meta(),
+          items,
+        }
+      })
+      .collect();
+
+    self.create_queues_folder();
+    let file = self.volatile_queues_file();
+    info!("Saving {} volatile queues to {}", queues.len(), file);
+    write_file_atomic(
+      &file,
+      &format!("{}.bak", file),
+      serde_json::to_string(&queues).expect("JSON stringify error"),
+    );
+  }
+
+  // Recreates the volatile queues saved by save_volatile_queues
+  pub fn read_volatile_queues(&self) {
+    let file = self.volatile_queues_file();
+    if !file_exists(&file) {
+      return;
+    }
+
+    let content = read_to_string(&file).expect("Couldn't read volatile queues file");
+    let queues: Vec<VolatileQueue> = match serde_json::from_str(&content) {
+      Ok(queues) => queues,
+      Err(_) => {
+        warn!("Volatile queues file is corrupted, skipping...");
+        return;
+      }
+    };
+
+    let mut queue_map = self.queues();
+    for volatile_queue in queues {
+      if queue_map.contains_key(&volatile_queue.name) {
+        warn!("Queue {} already exists, skipping...", volatile_queue.name);
+        continue;
+      }
+      info!("Recreating volatile queue {}", volatile_queue.name);
+      let queue = Queue::from_volatile_snapshot(
+        volatile_queue.name.clone(),
+        self.queue_folder(&volatile_queue.name),
+        Arc::downgrade(&self.queues),
+        volatile_queue.meta,
+        volatile_queue.items,
+      );
+      queue_map.insert(volatile_queue.name, queue);
+    }
+
+    // The queues live in memory again, don't restore stale state on the next start
+    remove_file(&file).ok();
+    remove_file(format!("{}.bak", file)).ok();
+  }
+}
@@ -0,0 +1,9 @@
+// Set by build.rs
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+pub const GIT_COMMIT: &str = env!("CORINTH_GIT_COMMIT");
+const BUILD_TIMESTAMP: &str = env!("CORINTH_BUILD_TIMESTAMP");
+
+// Unix timestamp in milliseconds
+pub fn build_timestamp() -> u64 {
+  BUILD_TIMESTAMP.parse::<u64>().unwrap_or(0) * 1000
+}
@@ -0,0 +1,575 @@
+use crate::auth::{parse_acl_entry, AclRule};
+use crate::env::apply_env_overrides;
+use crate::logging::{build_log_filter, validate_log_filter, validate_log_format};
+use corinth::queue::is_valid_requeue_time;
+use lazy_static::lazy_static;
+use serde_derive::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::net::ToSocketAddrs;
+use std::path::PathBuf;
+use std::sync::{RwLock, RwLockReadGuard};
+use structopt::StructOpt;
+
+// Config file used if neither --config nor CORINTH_CONFIG is given (and the file exists)
+const DEFAULT_CONFIG_FILE: &str = "corinth.toml";
+
+#[derive(StructOpt, Default)]
+#[structopt(name = "corinth", about = "Message queue server")]
+pub struct Cli {
+  /// TOML config file (default: corinth.toml, if it exists)
+  #[structopt(short, long, parse(from_os_str))]
+  pub config: Option<PathBuf>,
+
+  /// Host to listen on
+  #[structopt(long)]
+  pub host: Option<String>,
+
+  /// Port to listen on
+  #[structopt(short, long)]
+  pub port: Option<u16>,
+
+  /// Additional port serving plain HTTP
+  #[structopt(long)]
+  pub plain_port: Option<u16>,
+
+  /// Address (host:port) to listen on instead of host & port, can be repeated
+  #[structopt(long, number_of_values = 1)]
+  pub listen: Vec<String>,
+
+  /// Unix domain socket to listen on
+  #[structopt(long)]
+  pub unix_socket: Option<String>,
+
+  /// Folder queues are stored in
+  #[structopt(long)]
+  pub data_folder: Option<String>,
+
+  /// Compaction interval of persistent queues (in seconds, 0 to disable)
+  #[structopt(long)]
+  pub compact_interval: Option<u64>,
+
+  /// PEM certificate chain, serves HTTPS if set
+  #[structopt(long)]
+  pub tls_cert: Option<String>,
+
+  /// PEM private key
+  #[structopt(long)]
+  pub tls_key: Option<String>,
+
+  /// Log level (error, warn, info, debug, trace or off)
+  #[structopt(long)]
+  pub log_level: Option<String>,
+
+  /// Log output format (text or json)
+  #[structopt(long)]
+  pub log_format: Option<String>,
+
+  /// Validate the configuration and exit
+  #[structopt(long)]
+  pub check_config: bool,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+  pub host: String,
+  pub port: u16,
+  pub plain_port: Option<u16>,
+  // Addresses (host:port) to listen on, replaces host & port if set
+  pub listen: Option<Vec<String>>,
+  // Path of a Unix domain socket to listen on (plain HTTP)
+  pub unix_socket: Option<String>,
+  // Time (in seconds) running requests get to finish when shutting down
+  pub shutdown_timeout: u64,
+}
+
+impl Default for ServerConfig {
+  fn default() -> Self {
+    ServerConfig {
+      host: String::from("0.0.0.0"),
+      port: 44444,
+      plain_port: None,
+      listen: None,
+      unix_socket: None,
+      shutdown_timeout: 30,
+    }
+  }
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageConfig {
+  pub data_folder: String,
+  pub compact_interval: u64,
+  pub meta_flush_interval: u64,
+  // off, definitions or contents
+  pub volatile_snapshot: String,
+  // Snapshot archive to restore the data folder from on startup
+  pub restore_snapshot: Option<String>,
+  // Free disk space (in bytes) below which the server reports not ready, 0 to disable
+  pub min_free_space: u64,
+}
+
+impl Default for StorageConfig {
+  fn default() -> Self {
+    StorageConfig {
+      data_folder: String::from(".corinth"),
+      compact_interval: 86400,
+      meta_flush_interval: 5,
+      volatile_snapshot: String::from("off"),
+      restore_snapshot: None,
+      min_free_space: 104_857_600,
+    }
+  }
+}
+
+#[derive(Deserialize, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct TlsConfig {
+  pub cert: Option<String>,
+  pub key: Option<String>,
+  pub client_ca: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+  pub admin_keys: Vec<String>,
+  pub data_keys: Vec<String>,
+  // key:operations:pattern
+  pub acl: Vec<String>,
+  // Parsed acl entries by API key, set by validate
+  #[serde(skip)]
+  pub rules: Vec<(String, AclRule)>,
+}
+
+// Settings of queues created without explicit values
+#[derive(Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct QueueDefaults {
+  // In seconds, may be fractional
+  pub requeue_time: f64,
+  pub deduplication_time: u32,
+  pub max_length: u64,
+  pub persistent: bool,
+  pub dead_letter_threshold: u16,
+}
+
+impl Default for QueueDefaults {
+  fn default() -> Self {
+    QueueDefaults {
+      requeue_time: 300.0,
+      deduplication_time: 300,
+      max_length: 0,
+      persistent: true,
+      dead_letter_threshold: 3,
+    }
+  }
+}
+
+// Queue that is created (or reconciled) on startup
+// Unset settings use the queue defaults when creating the queue and are not reconciled
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct QueueDeclaration {
+  pub name: String,
+  pub persistent: Option<bool>,
+  pub requeue_time: Option<f64>,
+  pub deduplication_time: Option<u32>,
+  pub max_length: Option<u64>,
+  pub dead_letter_queue: Option<String>,
+  pub dead_letter_threshold: Option<u16>,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Limits {
+  pub max_queue_name_length: usize,
+  // Messages per enqueue request
+  pub max_enqueue_batch: usize,
+  // Size of JSON request bodies (in bytes)
+  pub max_body_size: usize,
+}
+
+impl Default for Limits {
+  fn default() -> Self {
+    Limits {
+      max_queue_name_length: 64,
+      max_enqueue_batch: 255,
+      max_body_size: 262_144,
+    }
+  }
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingConfig {
+  // Log level or filter (e.g. "info" or "warn,corinth::queue=debug")
+  pub level: String,
+  // text or json (one object per line)
+  pub format: String,
+  // Levels of single modules (e.g. "corinth::access" = "off")
+  pub modules: BTreeMap<String, String>,
+}
+
+impl LoggingConfig {
+  // Filter passed to the logger, combines level & modules
+  pub fn filter(&self) -> String {
+    build_log_filter(&self.level, &self.modules)
+  }
+}
+
+impl Default for LoggingConfig {
+  fn default() -> Self {
+    LoggingConfig {
+      level: String::from("info"),
+      format: String::from("text"),
+      modules: BTreeMap::new(),
+    }
+  }
+}
+
+#[derive(Deserialize, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+  pub server: ServerConfig,
+  pub storage: StorageConfig,
+  pub tls: TlsConfig,
+  pub auth: AuthConfig,
+  pub queue_defaults: QueueDefaults,
+  pub limits: Limits,
+  pub logging: LoggingConfig,
+  pub queues: Vec<QueueDeclaration>,
+}
+
+lazy_static! {
+  static ref CONFIG: RwLock<Config> = RwLock::new(Config::default());
+}
+
+pub fn config() -> RwLockReadGuard<'static, Config> {
+  CONFIG.read().unwrap()
+}
+
+pub fn set_config(config: Config) {
+  *CONFIG.write().unwrap() = config;
+}
+
+fn read_config_file(path: &PathBuf) -> Result<Config, String> {
+  let text = fs::read_to_string(path)
+    .map_err(|error| format!("Could not read config file {}: {}", path.display(), error))?;
+  toml::from_str(&text)
+    .map_err(|error| format!("Invalid config file {}: {}", path.display(), error))
+}
+
+fn apply_cli_overrides(config: &mut Config, cli: &Cli) {
+  if let Some(host) = &cli.host {
+    config.server.host = host.clone();
+  }
+  if let Some(port) = cli.port {
+    config.server.port = port;
+  }
+  if let Some(plain_port) = cli.plain_port {
+    config.server.plain_port = Some(plain_port);
+  }
+  if !cli.listen.is_empty() {
+    config.server.listen = Some(cli.listen.clone());
+  }
+  if let Some(unix_socket) = &cli.unix_socket {
+    config.server.unix_socket = Some(unix_socket.clone());
+  }
+  if let Some(data_folder) = &cli.data_folder {
+    config.storage.data_folder = data_folder.clone();
+  }
+  if let Some(compact_interval) = cli.compact_interval {
+    config.storage.compact_interval = compact_interval;
+  }
+  if let Some(cert) = &cli.tls_cert {
+    config.tls.cert = Some(cert.clone());
+  }
+  if let Some(key) = &cli.tls_key {
+    config.tls.key = Some(key.clone());
+  }
+  if let Some(level) = &cli.log_level {
+    config.logging.level = level.clone();
+  }
+  if let Some(format) = &cli.log_format {
+    config.logging.format = format.clone();
+  }
+}
+
+fn validate_listeners(server: &ServerConfig) -> Result<(), String> {
+  if let Some(addresses) = &server.listen {
+    for address in addresses {
+      if address.to_socket_addrs().is_err() {
+        return Err(format!("Invalid listen address \"{}\"", address));
+      }
+    }
+  }
+  if cfg!(not(unix)) && server.unix_socket.is_some() {
+    return Err(String::from(
+      "server.unix_socket is only supported on Unix systems",
+    ));
+  }
+
+  let num_tcp_listeners = server
+    .listen
+    .as_ref()
+    .map_or(1, |addresses| addresses.len());
+  if num_tcp_listeners == 0 && server.plain_port.is_none() && server.unix_socket.is_none() {
+    return Err(String::from("No address to listen on"));
+  }
+  Ok(())
+}
+
+fn validate(config: &mut Config) -> Result<(), String> {
+  if config.server.host.is_empty() {
+    return Err(String::from("server.host must not be empty"));
+  }
+  validate_listeners(&config.server)?;
+  if !["off", "definitions", "contents"].contains(&config.storage.volatile_snapshot.as_str()) {
+    return Err(format!(
+      "storage.volatile_snapshot must be off, definitions or contents, got \"{}\"",
+      config.storage.volatile_snapshot
+    ));
+  }
+  if config.storage.meta_flush_interval == 0 {
+    return Err(String::from(
+      "storage.meta_flush_interval must be at least 1",
+    ));
+  }
+  if config.tls.cert.is_some() != config.tls.key.is_some() {
+    return Err(String::from("tls.cert and tls.key have to be set together"));
+  }
+  if config.tls.client_ca.is_some() && config.tls.cert.is_none() {
+    return Err(String::from("tls.client_ca requires tls.cert and tls.key"));
+  }
+  if config.limits.max_queue_name_length == 0 || config.limits.max_enqueue_batch == 0 {
+    return Err(String::from("limits must be at least 1"));
+  }
+
+  if !is_valid_requeue_time(config.queue_defaults.requeue_time) {
+    return Err(String::from(
+      "queue_defaults.requeue_time must be a non-negative number of seconds",
+    ));
+  }
+
+  validate_log_filter(&config.logging.filter())?;
+  validate_log_format(&config.logging.format)?;
+  validate_queues(config)?;
+
+  // Parsed once here, so requests never have to parse (or fail on) the ACL
+  config.auth.rules = parse_acl(&config.auth)?;
+
+  Ok(())
+}
+
+fn parse_acl(auth: &AuthConfig) -> Result<Vec<(String, AclRule)>, String> {
+  let mut rules = Vec::new();
+  for entry in &auth.acl {
+    let (key, rule) = parse_acl_entry(entry).ok_or(format!("Invalid ACL entry \"{}\"", entry))?;
+    if !auth.admin_keys.contains(&key) && !auth.data_keys.contains(&key) {
+      return Err(format!(
+        "ACL entry \"{}\" references an unknown API key",
+        entry
+      ));
+    }
+    rules.push((key, rule));
+  }
+  Ok(rules)
+}
+
+fn validate_queues(config: &Config) -> Result<(), String> {
+  let mut names: Vec<&String> = Vec::new();
+  for queue in &config.queues {
+    if queue.name.is_empty() || queue.name.len() > config.limits.max_queue_name_length {
+      return Err(format!("Invalid queue name \"{}\"", queue.name));
+    }
+    if names.contains(&&queue.name) {
+      return Err(format!(
+        "Queue \"{}\" is declared more than once",
+        queue.name
+      ));
+    }
+    names.push(&queue.name);
+
+    if queue.dead_letter_queue.as_ref() == Some(&queue.name) {
+      return Err(format!(
+        "Queue \"{}\" cannot be its own dead letter queue",
+        queue.name
+      ));
+    }
+    if queue
+      .requeue_time
+      .is_some_and(|secs| !is_valid_requeue_time(secs))
+    {
+      return Err(format!(
+        "Queue \"{}\" has an invalid requeue_time",
+        queue.name
+      ));
+    }
+    if queue.dead_letter_threshold.is_some() && queue.dead_letter_queue.is_none() {
+      return Err(format!(
+        "Queue \"{}\" sets dead_letter_threshold without dead_letter_queue",
+        queue.name
+      ));
+    }
+  }
+  Ok(())
+}
+
+// Builds the configuration, later sources override earlier ones:
+// defaults, config file, environment variables (CORINTH_*), command-line flags
+pub fn load_config(cli: &Cli) -> Result<Config, String> {
+  let explicit_file = cli
+    .config
+    .clone()
+    .or_else(|| std::env::var("CORINTH_CONFIG").ok().map(PathBuf::from));
+
+  let mut config = match explicit_file {
+    Some(path) => read_config_file(&path)?,
+    None => {
+      let default_path = PathBuf::from(DEFAULT_CONFIG_FILE);
+      if default_path.exists() {
+        read_config_file(&default_path)?
+      } else {
+        Config::default()
+      }
+    }
+  };
+
+  apply_env_overrides(&mut config)?;
+  apply_cli_overrides(&mut config, cli);
+  validate(&mut config)?;
+  Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::env;
+  use std::io::Write;
+  use std::sync::Mutex;
+  use tempfile::NamedTempFile;
+
+  lazy_static! {
+    // Environment variables are shared by all tests
+    static ref ENV_LOCK: Mutex<()> = Mutex::new(());
+  }
+
+  fn config_file(text: &str) -> NamedTempFile {
+    let mut file = NamedTempFile::new().unwrap();
+    file.write_all(text.as_bytes()).unwrap();
+    file
+  }
+
+  fn cli(file: &NamedTempFile) -> Cli {
+    Cli {
+      config: Some(file.path().to_path_buf()),
+      ..Cli::default()
+    }
+  }
+
+  #[test]
+  fn layering() {
+    let _lock = ENV_LOCK.lock().unwrap();
+    let file = config_file(
+      "[server]\nport = 7001\nhost = \"127.0.0.1\"\n\n[storage]\ncompact_interval = 10\n",
+    );
+
+    let defaults = Config::default();
+    let config = load_config(&cli(&file)).unwrap();
+    assert_eq!(config.server.port, 7001);
+    assert_eq!(config.server.host, "127.0.0.1");
+    assert_eq!(
+      config.limits.max_enqueue_batch,
+      defaults.limits.max_enqueue_batch
+    );
+
+    env::set_var("CORINTH_PORT", "7002");
+    env::set_var("CORINTH_COMPACT_INTERVAL", "20");
+    let config = load_config(&cli(&file)).unwrap();
+    assert_eq!(config.server.port, 7002);
+    assert_eq!(config.storage.compact_interval, 20);
+    assert_eq!(config.server.host, "127.0.0.1");
+
+    let config = load_config(&Cli {
+      port: Some(7003),
+      ..cli(&file)
+    })
+    .unwrap();
+    env::remove_var("CORINTH_PORT");
+    env::remove_var("CORINTH_COMPACT_INTERVAL");
+    assert_eq!(config.server.port, 7003);
+    assert_eq!(config.storage.compact_interval, 20);
+  }
+
+  #[test]
+  fn invalid_env_value() {
+    let _lock = ENV_LOCK.lock().unwrap();
+    let file = config_file("");
+
+    env::set_var("CORINTH_PORT", "http");
+    let result = load_config(&cli(&file));
+    env::remove_var("CORINTH_PORT");
+    assert_eq!(
+      result.err().unwrap(),
+      "Invalid value for CORINTH_PORT: \"http\""
+    );
+  }
+
+  #[test]
+  fn validation() {
+    let _lock = ENV_LOCK.lock().unwrap();
+    let invalid = [
+      (
+        "[limits]\nmax_enqueue_batch = 0\n",
+        "limits must be at least 1",
+      ),
+      (
+        "[limits]\nmax_queue_name_length = 0\n",
+        "limits must be at least 1",
+      ),
+      (
+        "[storage]\nmeta_flush_interval = 0\n",
+        "storage.meta_flush_interval must be at least 1",
+      ),
+      (
+        "[tls]\ncert = \"cert.pem\"\n",
+        "tls.cert and tls.key have to be set together",
+      ),
+      (
+        "[auth]\nacl = [\"nobody:consume:*\"]\n",
+        "ACL entry \"nobody:consume:*\" references an unknown API key",
+      ),
+      (
+        "[auth]\ndata_keys = [\"worker\"]\nacl = [\"worker:read:*\"]\n",
+        "Invalid ACL entry \"worker:read:*\"",
+      ),
+      (
+        "[[queues]]\nname = \"jobs\"\n\n[[queues]]\nname = \"jobs\"\n",
+        "Queue \"jobs\" is declared more than once",
+      ),
+    ];
+    for (text, message) in invalid.iter() {
+      let file = config_file(text);
+      assert_eq!(load_config(&cli(&file)).err().unwrap(), *message);
+    }
+
+    let file = config_file("[limits]\nmax_enqueue_batch = -1\n");
+    assert!(load_config(&cli(&file))
+      .err()
+      .unwrap()
+      .starts_with("Invalid config file"));
+  }
+
+  #[test]
+  fn parsed_acl() {
+    let _lock = ENV_LOCK.lock().unwrap();
+    let file = config_file("[auth]\ndata_keys = [\"worker\"]\nacl = [\"worker:consume:jobs-*\"]\n");
+
+    let config = load_config(&cli(&file)).unwrap();
+    assert_eq!(config.auth.rules.len(), 1);
+    assert_eq!(config.auth.rules[0].0, "worker");
+  }
+}
@@ -1,18 +1,183 @@
-// use chrono::{DateTime, Local};
-use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use lazy_static::lazy_static;
+#[cfg(debug_assertions)]
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(debug_assertions)]
+use std::sync::{Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-// pub fn iso_date() -> String {
-//   let now: DateTime<Local> = Local::now();
-//   now.to_rfc2822()
-// }
+// All time-based logic (timestamps, ack & dedup expiration, compaction) goes through this clock
+// It follows the system time, but in debug builds it can be advanced (tests & the debug
+// clock route), which wakes up waiting timers instead of waiting in real time
+struct Clock {
+  // Added to the system time
+  offset_ms: AtomicU64,
+  // Deadlines of timers that have not finished yet, by timer id
+  #[cfg(debug_assertions)]
+  timers: Mutex<HashMap<u64, u64>>,
+  // Notified when the clock is advanced, wakes up waiting timers
+  #[cfg(debug_assertions)]
+  advanced: Condvar,
+  // Notified when a timer finishes, wakes up advance_clock only
+  #[cfg(debug_assertions)]
+  finished: Condvar,
+  #[cfg(debug_assertions)]
+  next_timer_id: AtomicU64,
+}
 
-pub fn timestamp() -> u64 {
+lazy_static! {
+  static ref CLOCK: Clock = Clock {
+    offset_ms: AtomicU64::new(0),
+    #[cfg(debug_assertions)]
+    timers: Mutex::new(HashMap::new()),
+    #[cfg(debug_assertions)]
+    advanced: Condvar::new(),
+    #[cfg(debug_assertions)]
+    finished: Condvar::new(),
+    #[cfg(debug_assertions)]
+    next_timer_id: AtomicU64::new(0),
+  };
+}
+
+// Longest time advance_clock waits for due timers to finish
+#[cfg(debug_assertions)]
+const MAX_TIMER_WAIT: Duration = Duration::from_secs(10);
+
+fn system_timestamp() -> u64 {
   SystemTime::now()
     .duration_since(UNIX_EPOCH)
     .unwrap()
-    .as_secs()
+    .as_millis() as u64
+}
+
+// Unix timestamp in milliseconds
+pub fn timestamp() -> u64 {
+  system_timestamp() + CLOCK.offset_ms.load(Ordering::SeqCst)
 }
 
 pub fn elapsed_secs(start_time: Instant) -> u64 {
   start_time.elapsed().as_secs()
 }
+
+pub fn elapsed_ms(start_time: Instant) -> u64 {
+  start_time.elapsed().as_millis() as u64
+}
+
+#[cfg(debug_assertions)]
+fn duration_ms(duration: Duration) -> u64 {
+  duration.as_millis() as u64
+}
+
+// Registered timer, finished when dropped (also if its task panics)
+#[cfg(debug_assertions)]
+struct Timer {
+  id: u64,
+  deadline: u64,
+}
+
+#[cfg(debug_assertions)]
+impl Timer {
+  // Registers a timer, so advance_clock waits for it once it is due
+  fn register(delay: Duration) -> Timer {
+    let id = CLOCK.next_timer_id.fetch_add(1, Ordering::SeqCst);
+    let deadline = timestamp() + duration_ms(delay);
+    CLOCK.timers.lock().unwrap().insert(id, deadline);
+    Timer { id, deadline }
+  }
+
+  // Blocks until the clock reaches the deadline
+  fn wait(&self) {
+    let mut timers = CLOCK.timers.lock().unwrap();
+    loop {
+      let now = timestamp();
+      if now >= self.deadline {
+        return;
+      }
+      timers = CLOCK
+        .advanced
+        .wait_timeout(timers, Duration::from_millis(self.deadline - now))
+        .unwrap()
+        .0;
+    }
+  }
+}
+
+#[cfg(debug_assertions)]
+impl Drop for Timer {
+  fn drop(&mut self) {
+    // Don't panic while unwinding because of a poisoned lock
+    if let Ok(mut timers) = CLOCK.timers.lock() {
+      timers.remove(&self.id);
+    }
+    CLOCK.finished.notify_all();
+  }
+}
+
+// The clock cannot be advanced in release builds, so timers simply sleep
+#[cfg(not(debug_assertions))]
+struct Timer {
+  delay: Duration,
+}
+
+#[cfg(not(debug_assertions))]
+impl Timer {
+  fn register(delay: Duration) -> Timer {
+    Timer { delay }
+  }
+
+  fn wait(&self) {
+    thread::sleep(self.delay);
+  }
+}
+
+// Runs the task on its own thread once the delay has passed on the clock
+pub fn schedule<F>(delay: Duration, task: F)
+where
+  F: FnOnce() + Send + 'static,
+{
+  // Registered before the thread starts, so advancing the clock right after cannot miss it
+  // The timer is moved into the thread & dropped once the task has finished
+  let timer = Timer::register(delay);
+  thread::spawn(move || {
+    timer.wait();
+    task();
+  });
+}
+
+// Waits for the delay on the clock, then runs the task on the current thread
+// Used by interval loops
+pub fn after<F, R>(delay: Duration, task: F) -> R
+where
+  F: FnOnce() -> R,
+{
+  // Dropped at the end of the function, after the task
+  let timer = Timer::register(delay);
+  timer.wait();
+  task()
+}
+
+// Moves the clock forward and waits until all timers that became due have finished,
+// so their effects (requeued messages, expired deduplication IDs...) are visible afterwards
+#[cfg(debug_assertions)]
+pub fn advance_clock(duration: Duration) -> u64 {
+  CLOCK
+    .offset_ms
+    .fetch_add(duration_ms(duration), Ordering::SeqCst);
+  CLOCK.advanced.notify_all();
+
+  let started = Instant::now();
+  let mut timers = CLOCK.timers.lock().unwrap();
+  while started.elapsed() < MAX_TIMER_WAIT {
+    let now = timestamp();
+    if timers.values().all(|deadline| *deadline > now) {
+      break;
+    }
+    timers = CLOCK
+      .finished
+      .wait_timeout(timers, Duration::from_millis(10))
+      .unwrap()
+      .0;
+  }
+  timestamp()
+}
@@ -1,37 +1,211 @@
-use std::convert::TryInto;
+use crate::auth::AclRule;
+use crate::config::{config, Config};
+use corinth::BrokerOptions;
 use std::env;
+use std::str::FromStr;
 
-pub fn try_env_to_uint(name: String) -> Option<u64> {
-  let str_value = env::var(name).ok();
-  if str_value.is_some() {
-    return Some(
-      str_value
-        .unwrap()
-        .parse::<u64>()
-        .expect("Invalid env variable!"),
-    );
+fn parse_env<T: FromStr>(name: &str) -> Result<Option<T>, String> {
+  match env::var(name) {
+    Ok(value) => value
+      .trim()
+      .parse::<T>()
+      .map(Some)
+      .map_err(|_| format!("Invalid value for {}: \"{}\"", name, value)),
+    Err(_) => Ok(None),
   }
-  None
 }
 
-pub fn get_port() -> u16 {
-  let num = try_env_to_uint(String::from("CORINTH_PORT"));
-  if num.is_some() {
-    num.unwrap().try_into().expect("Invalid port value")
+fn env_to_list(name: &str) -> Option<Vec<String>> {
+  env::var(name).ok().map(|value| {
+    value
+      .split(',')
+      .map(|item| item.trim())
+      .filter(|item| !item.is_empty())
+      .map(String::from)
+      .collect()
+  })
+}
+
+// Environment variables override the config file
+pub fn apply_env_overrides(config: &mut Config) -> Result<(), String> {
+  if let Ok(host) = env::var("CORINTH_HOST") {
+    config.server.host = host;
+  }
+  if let Some(port) = parse_env("CORINTH_PORT")? {
+    config.server.port = port;
+  }
+  if let Some(plain_port) = parse_env("CORINTH_PLAIN_PORT")? {
+    config.server.plain_port = Some(plain_port);
+  }
+  if let Some(addresses) = env_to_list("CORINTH_LISTEN") {
+    config.server.listen = Some(addresses);
+  }
+  if let Ok(unix_socket) = env::var("CORINTH_UNIX_SOCKET") {
+    config.server.unix_socket = Some(unix_socket);
+  }
+  if let Some(shutdown_timeout) = parse_env("CORINTH_SHUTDOWN_TIMEOUT")? {
+    config.server.shutdown_timeout = shutdown_timeout;
+  }
+
+  if let Ok(data_folder) = env::var("CORINTH_BASE_FOLDER") {
+    config.storage.data_folder = data_folder;
+  }
+  if let Some(compact_interval) = parse_env("CORINTH_COMPACT_INTERVAL")? {
+    config.storage.compact_interval = compact_interval;
+  }
+  if let Some(meta_flush_interval) = parse_env("CORINTH_META_FLUSH_INTERVAL")? {
+    config.storage.meta_flush_interval = meta_flush_interval;
+  }
+  if let Ok(mode) = env::var("CORINTH_VOLATILE_SNAPSHOT") {
+    config.storage.volatile_snapshot = mode;
+  }
+  if let Ok(snapshot) = env::var("CORINTH_RESTORE_SNAPSHOT") {
+    config.storage.restore_snapshot = Some(snapshot);
+  }
+  if let Some(min_free_space) = parse_env("CORINTH_MIN_FREE_SPACE")? {
+    config.storage.min_free_space = min_free_space;
+  }
+
+  if let Ok(cert) = env::var("CORINTH_TLS_CERT") {
+    config.tls.cert = Some(cert);
+  }
+  if let Ok(key) = env::var("CORINTH_TLS_KEY") {
+    config.tls.key = Some(key);
+  }
+  if let Ok(client_ca) = env::var("CORINTH_TLS_CLIENT_CA") {
+    config.tls.client_ca = Some(client_ca);
+  }
+
+  if let Some(keys) = env_to_list("CORINTH_ADMIN_KEYS") {
+    config.auth.admin_keys = keys;
+  }
+  if let Some(keys) = env_to_list("CORINTH_DATA_KEYS") {
+    config.auth.data_keys = keys;
+  }
+  if let Some(acl) = env_to_list("CORINTH_ACL") {
+    config.auth.acl = acl;
+  }
+
+  if let Ok(level) = env::var("CORINTH_LOG_LEVEL") {
+    config.logging.level = level;
+  }
+  if let Ok(format) = env::var("CORINTH_LOG_FORMAT") {
+    config.logging.format = format;
+  }
+
+  Ok(())
+}
+
+// IPv6 hosts need brackets to be combined with a port
+fn format_address(host: &str, port: u16) -> String {
+  if host.contains(':') && !host.starts_with('[') {
+    format!("[{}]:{}", host, port)
   } else {
-    44444
+    format!("{}:{}", host, port)
+  }
+}
+
+// Addresses the server listens on, defaults to host & port
+pub fn get_listen_addresses() -> Vec<String> {
+  let config = config();
+  match &config.server.listen {
+    Some(addresses) => addresses.clone(),
+    None => vec![format_address(&config.server.host, config.server.port)],
   }
 }
 
+// Additional address serving plain HTTP (e.g. next to HTTPS on the main port)
+pub fn get_plain_address() -> Option<String> {
+  let config = config();
+  config
+    .server
+    .plain_port
+    .map(|port| format_address(&config.server.host, port))
+}
+
+pub fn get_unix_socket() -> Option<String> {
+  config().server.unix_socket.clone()
+}
+
 pub fn get_compaction_interval() -> u64 {
-  let num = try_env_to_uint(String::from("CORINTH_COMPACT_INTERVAL"));
-  if num.is_some() {
-    num.unwrap().try_into().expect("Invalid port value")
-  } else {
-    86400
+  config().storage.compact_interval
+}
+
+// Time (in seconds) running requests get to finish when shutting down
+pub fn get_shutdown_timeout() -> u64 {
+  config().server.shutdown_timeout
+}
+
+pub fn get_metadata_flush_interval() -> u64 {
+  config().storage.meta_flush_interval
+}
+
+// Snapshot archive to restore the data folder from on startup
+pub fn get_restore_snapshot() -> Option<String> {
+  config().storage.restore_snapshot.clone()
+}
+
+// Free disk space (in bytes) the data folder needs to be ready
+pub fn get_min_free_space() -> u64 {
+  config().storage.min_free_space
+}
+
+#[derive(PartialEq)]
+pub enum VolatileSnapshotMode {
+  // Volatile queues are lost on shutdown
+  Off,
+  // Only the queue settings are kept
+  Definitions,
+  // Queue settings and messages are kept
+  Contents,
+}
+
+pub fn get_volatile_snapshot_mode() -> VolatileSnapshotMode {
+  // Validated when loading the config
+  match config().storage.volatile_snapshot.as_str() {
+    "definitions" => VolatileSnapshotMode::Definitions,
+    "contents" => VolatileSnapshotMode::Contents,
+    _ => VolatileSnapshotMode::Off,
   }
 }
 
+// API keys that may access all routes
+pub fn get_admin_keys() -> Vec<String> {
+  config().auth.admin_keys.clone()
+}
+
+// API keys that may only produce & consume messages
+pub fn get_data_keys() -> Vec<String> {
+  config().auth.data_keys.clone()
+}
+
+// Queue access control rules by API key, parsed from the ACL entries on startup
+pub fn get_acl_rules() -> Vec<(String, AclRule)> {
+  config().auth.rules.clone()
+}
+
+// PEM certificate chain & private key, serves HTTPS if set
+pub fn get_tls_cert() -> Option<String> {
+  config().tls.cert.clone()
+}
+
+pub fn get_tls_key() -> Option<String> {
+  config().tls.key.clone()
+}
+
+// PEM CA certificate(s) client certificates have to be signed by (mutual TLS)
+pub fn get_tls_client_ca() -> Option<String> {
+  config().tls.client_ca.clone()
+}
+
 pub fn data_folder() -> String {
-  env::var("CORINTH_BASE_FOLDER").unwrap_or(String::from(".corinth"))
+  config().storage.data_folder.clone()
+}
+
+pub fn get_broker_options() -> BrokerOptions {
+  BrokerOptions {
+    data_folder: data_folder(),
+    compact_interval: get_compaction_interval(),
+    meta_flush_interval: get_metadata_flush_interval(),
+  }
 }
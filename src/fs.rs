@@ -1,7 +1,5 @@
-use crate::env::data_folder;
-use std::fs::create_dir_all;
-use std::fs::OpenOptions;
-use std::io::Write;
+use std::fs::{rename, File, OpenOptions};
+use std::io::{self, Write};
 use std::path::Path;
 
 pub fn file_exists(path: &String) -> bool {
@@ -17,9 +15,56 @@ pub fn append_to_file(path: &String, text: String) {
   file.write_all(text.as_bytes()).expect("Append failed");
 }
 
-pub fn create_queues_folder() -> String {
-  let folder = format!("{}/queues", data_folder());
-  create_dir_all(&folder).ok();
-  eprintln!("Created queues folder");
-  folder
+// Makes sure everything appended to the file is on disk
+pub fn sync_file(path: &String) {
+  if let Ok(file) = OpenOptions::new().append(true).open(path) {
+    file.sync_all().expect("Sync failed");
+  }
+}
+
+// Syncs the folder entry itself, so renames inside of it are durable
+fn sync_parent_folder(path: &String) {
+  if let Some(parent) = Path::new(path).parent() {
+    if let Ok(folder) = File::open(parent) {
+      folder.sync_all().ok();
+    }
+  }
+}
+
+// Replaces the file's content without ever leaving a partially written file behind
+// The text is written into a temp file and synced, then renamed to the real path
+// The previous version of the file (if any) is kept as backup
+pub fn write_file_atomic(path: &String, backup_path: &String, text: String) {
+  let temp_path = format!("{}~", path);
+
+  let mut file = File::create(&temp_path).expect("Cannot create temp file");
+  file.write_all(text.as_bytes()).expect("Write failed");
+  file.sync_all().expect("Sync failed");
+
+  if file_exists(path) {
+    rename(path, backup_path).expect("Failed to back up file");
+  }
+  rename(&temp_path, path).expect("Failed to replace file");
+  sync_parent_folder(path);
+}
+
+// Removes a socket file left behind by a server that did not shut down cleanly
+// Fails if another process still accepts connections on it, as binding would replace it
+#[cfg(unix)]
+pub fn remove_stale_socket(path: &str) -> io::Result<()> {
+  use std::os::unix::fs::FileTypeExt;
+  use std::os::unix::net::UnixStream;
+
+  if let Ok(metadata) = std::fs::metadata(path) {
+    if metadata.file_type().is_socket() {
+      if UnixStream::connect(path).is_ok() {
+        return Err(io::Error::new(
+          io::ErrorKind::AddrInUse,
+          "Socket is in use by another process",
+        ));
+      }
+      std::fs::remove_file(path)?;
+    }
+  }
+  Ok(())
 }
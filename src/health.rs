@@ -0,0 +1,74 @@
+use crate::env::{data_folder, get_min_free_space};
+use crate::global_data::queues_loaded;
+use crate::shutdown::is_shutting_down;
+use corinth_api::ReadinessCheck;
+use std::fs::{create_dir_all, remove_file, write};
+use std::path::Path;
+
+// File written & removed to check that the data folder is writable
+const PROBE_FILE: &str = ".health_probe";
+
+fn readiness_check(name: &str, result: Result<String, String>) -> ReadinessCheck {
+  let (ok, message) = match result {
+    Ok(message) => (true, message),
+    Err(message) => (false, message),
+  };
+  ReadinessCheck {
+    name: String::from(name),
+    ok,
+    message,
+  }
+}
+
+fn check_loaded() -> Result<String, String> {
+  if queues_loaded() {
+    Ok(String::from("Queues are loaded"))
+  } else {
+    Err(String::from("Queues are still being loaded from disk"))
+  }
+}
+
+fn check_not_draining() -> Result<String, String> {
+  if is_shutting_down() {
+    Err(String::from("Server is shutting down, draining requests"))
+  } else {
+    Ok(String::from("Server is accepting requests"))
+  }
+}
+
+fn check_writable(folder: &str) -> Result<String, String> {
+  let probe = Path::new(folder).join(PROBE_FILE);
+  create_dir_all(folder)
+    .and_then(|_| write(&probe, b"ok"))
+    .and_then(|_| remove_file(&probe))
+    .map(|_| format!("{} is writable", folder))
+    .map_err(|error| format!("{} is not writable: {}", folder, error))
+}
+
+fn check_disk_space(folder: &str) -> Result<String, String> {
+  let min_free_space = get_min_free_space();
+  if min_free_space == 0 {
+    return Ok(String::from("Disk space check is disabled"));
+  }
+  let available = fs2::available_space(folder)
+    .map_err(|error| format!("Could not read free disk space: {}", error))?;
+  if available < min_free_space {
+    Err(format!(
+      "{} bytes free, at least {} required",
+      available, min_free_space
+    ))
+  } else {
+    Ok(format!("{} bytes free", available))
+  }
+}
+
+// The server is ready to take traffic if all checks pass
+pub fn check_readiness() -> Vec<ReadinessCheck> {
+  let folder = data_folder();
+  vec![
+    readiness_check("queues_loaded", check_loaded()),
+    readiness_check("not_draining", check_not_draining()),
+    readiness_check("data_folder_writable", check_writable(&folder)),
+    readiness_check("disk_space", check_disk_space(&folder)),
+  ]
+}
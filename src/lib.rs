@@ -0,0 +1,16 @@
+// Queue engine of the Corinth server, usable without the HTTP layer
+#[macro_use]
+extern crate serde_derive;
+
+pub mod broker;
+pub mod date;
+mod fs;
+pub mod queue;
+pub mod storage;
+
+pub use broker::{Broker, BrokerError, BrokerOptions, EnqueueResult, NewMessage};
+pub use queue::{Message, Queue, QueueDeadLetterSettings, QueueSettings};
+
+// Used by the server binary
+#[cfg(unix)]
+pub use fs::remove_stale_socket;
@@ -0,0 +1,127 @@
+use chrono::{SecondsFormat, Utc};
+use env_logger::fmt::Formatter;
+use env_logger::Builder;
+use log::kv::{Error, Key, Value, VisitSource};
+use log::Record;
+use serde_json::{json, Map};
+use std::collections::BTreeMap;
+use std::io::Write;
+
+const LEVELS: [&str; 6] = ["off", "error", "warn", "info", "debug", "trace"];
+
+// Target of the HTTP access log, can be filtered like a module
+pub const ACCESS_LOG_TARGET: &str = "corinth::access";
+
+fn validate_level(level: &str) -> Result<(), String> {
+  if LEVELS.contains(&level.to_lowercase().as_str()) {
+    Ok(())
+  } else {
+    Err(format!("Invalid log level \"{}\"", level))
+  }
+}
+
+// Checks a filter of the form "level" or "level,module=level,..."
+pub fn validate_log_filter(filter: &str) -> Result<(), String> {
+  for directive in filter
+    .split(',')
+    .map(|d| d.trim())
+    .filter(|d| !d.is_empty())
+  {
+    match directive.split_once('=') {
+      Some((module, level)) if !module.is_empty() => validate_level(level)?,
+      Some(_) => return Err(format!("Invalid log filter \"{}\"", directive)),
+      None => validate_level(directive)?,
+    }
+  }
+  Ok(())
+}
+
+// Combines the global level (or filter) with per-module levels
+pub fn build_log_filter(level: &str, modules: &BTreeMap<String, String>) -> String {
+  let mut directives = vec![level.to_string()];
+  directives.extend(
+    modules
+      .iter()
+      .map(|(module, level)| format!("{}={}", module, level)),
+  );
+  directives.join(",")
+}
+
+pub fn validate_log_format(format: &str) -> Result<(), String> {
+  match format {
+    "text" | "json" => Ok(()),
+    _ => Err(format!(
+      "Invalid log format \"{}\", expected text or json",
+      format
+    )),
+  }
+}
+
+// Collects the key-value pairs attached to a log record (e.g. request_id)
+struct Fields(Vec<(String, serde_json::Value)>);
+
+impl<'kvs> VisitSource<'kvs> for Fields {
+  fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), Error> {
+    let value = if let Some(num) = value.to_u64() {
+      json!(num)
+    } else if let Some(num) = value.to_f64() {
+      json!(num)
+    } else {
+      json!(value.to_string())
+    };
+    self.0.push((key.to_string(), value));
+    Ok(())
+  }
+}
+
+fn record_fields(record: &Record) -> Vec<(String, serde_json::Value)> {
+  let mut fields = Fields(Vec::new());
+  record.key_values().visit(&mut fields).ok();
+  fields.0
+}
+
+fn now() -> String {
+  Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+fn format_text(buf: &mut Formatter, record: &Record) -> std::io::Result<()> {
+  write!(
+    buf,
+    "[{} {:<5} {}] {}",
+    now(),
+    record.level(),
+    record.target(),
+    record.args()
+  )?;
+  for (key, value) in record_fields(record) {
+    match value {
+      serde_json::Value::String(text) => write!(buf, " {}={}", key, text)?,
+      other => write!(buf, " {}={}", key, other)?,
+    }
+  }
+  writeln!(buf)
+}
+
+// One JSON object per line
+fn format_json(buf: &mut Formatter, record: &Record) -> std::io::Result<()> {
+  let mut line = Map::new();
+  line.insert(String::from("timestamp"), json!(now()));
+  line.insert(String::from("level"), json!(record.level().to_string()));
+  line.insert(String::from("target"), json!(record.target()));
+  line.insert(String::from("message"), json!(record.args().to_string()));
+  for (key, value) in record_fields(record) {
+    line.insert(key, value);
+  }
+  writeln!(buf, "{}", serde_json::Value::Object(line))
+}
+
+pub fn init_logging(filter: &str, format: &str) {
+  let mut builder = Builder::new();
+  builder.parse_filters(filter);
+  if format == "json" {
+    builder.format(format_json);
+  } else {
+    builder.format(format_text);
+  }
+  builder.init();
+}
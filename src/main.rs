@@ -1,55 +1,269 @@
-#[macro_use]
-extern crate serde_derive;
-
-mod date;
+mod auth;
+mod build_info;
+mod config;
 mod env;
-mod fs;
 mod global_data;
-mod queue;
+mod health;
+mod logging;
+mod metrics;
+mod openapi;
+mod provision;
 mod response;
 mod routes;
+mod shutdown;
+mod snapshot;
+mod tls;
 
-use crate::env::get_port;
-use crate::global_data::get_start_time;
-use crate::global_data::read_queues_from_disk;
-use crate::routes::{
-  ack_handler, close_handler, compact_handler, create_queue_handler, delete_handler,
-  dequeue_handler, edit_queue_handler, enqueue_handler, favicon_handler, get_queue_handler,
-  index_handler, list_queues_handler, peek_handler, purge_handler, server_info_handler,
+#[cfg(test)]
+mod tests;
+
+use crate::auth::{auth_enabled, authorize, AuthError};
+use crate::config::{load_config, set_config, Cli};
+use crate::env::{
+  data_folder, get_broker_options, get_listen_addresses, get_plain_address, get_restore_snapshot,
+  get_shutdown_timeout, get_unix_socket,
 };
+use crate::global_data::get_start_time;
+use crate::global_data::{queues_loaded, set_queues_loaded};
+use crate::logging::{init_logging, ACCESS_LOG_TARGET};
+use crate::metrics::observe_request;
+use crate::provision::provision_queues;
+use crate::response::format_error;
+use crate::routes::configure_app;
+use crate::shutdown::{finish_shutdown, is_shutting_down, listen_for_signals, register_server};
+use crate::snapshot::restore_snapshot;
+use crate::tls::{listen_for_reload, tls_config};
 use actix_files::Files;
-use actix_web::{App, HttpServer};
+use actix_web::dev::Service;
+use actix_web::http::{HeaderName, HeaderValue};
+use actix_web::{rt, App, HttpResponse, HttpServer};
+#[cfg(unix)]
+use corinth::remove_stale_socket;
+use corinth::storage::lock_data_folder;
+use corinth::Broker;
+use futures::future::{ok, Either};
+use log::{error, info, warn};
+use oysterpack_uid::ulid::ulid_str;
+use std::panic;
+use std::process;
+use std::thread;
+use std::time::Instant;
+use structopt::StructOpt;
+
+const REQUEST_ID_HEADER: &str = "x-request-id";
+
+// Routes that are unavailable until the stored queues are loaded
+fn needs_queues(path: &str) -> bool {
+  path.starts_with("/queue") || path.starts_with("/snapshot")
+}
+
+// Probes keep answering during a drain, readiness reports the shutdown itself
+fn is_health_check(path: &str) -> bool {
+  path.starts_with("/health/")
+}
+
+fn load_queues(broker: Broker) {
+  let start = Instant::now();
+  // The server is already listening, so a broken data folder has to stop it here
+  if panic::catch_unwind(|| {
+    broker.load();
+    broker.read_volatile_queues();
+  })
+  .is_err()
+  {
+    error!("Failed to load queues from {}", data_folder());
+    process::exit(1);
+  }
+  if let Err(message) = provision_queues(&broker) {
+    error!("Failed to provision queues: {}", message);
+    process::exit(1);
+  }
+  set_queues_loaded();
+  info!("Loaded queues in {} ms", start.elapsed().as_millis());
+}
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-  read_queues_from_disk();
+  let cli = Cli::from_args();
+  let loaded_config = match load_config(&cli) {
+    Ok(loaded_config) => loaded_config,
+    Err(message) => {
+      eprintln!("Configuration error: {}", message);
+      process::exit(1);
+    }
+  };
+  if cli.check_config {
+    println!("Configuration is valid");
+    return Ok(());
+  }
+  init_logging(
+    &loaded_config.logging.filter(),
+    &loaded_config.logging.format,
+  );
+  set_config(loaded_config);
+  let broker = Broker::new(get_broker_options());
+  // Keeps the offline storage tool from rewriting files while the server runs
+  let _data_folder_lock = match lock_data_folder(&data_folder()) {
+    Ok(lock) => lock,
+    Err(error) => {
+      error!("Failed to lock data folder {}: {}", data_folder(), error);
+      process::exit(1);
+    }
+  };
 
-  let port = get_port();
-  let bind = format!("0.0.0.0:{}", port);
+  if let Some(snapshot) = get_restore_snapshot() {
+    if let Err(message) = restore_snapshot(&broker, &snapshot) {
+      error!("Failed to restore snapshot {}: {}", snapshot, message);
+      process::exit(1);
+    }
+  }
+  let tls = match tls_config() {
+    Ok(tls) => tls,
+    Err(message) => {
+      error!("Failed to load TLS certificate: {}", message);
+      process::exit(1);
+    }
+  };
 
-  eprintln!("Starting on port {}", port);
-  eprintln!("Start time: {:?}", get_start_time());
+  info!("Start time: {:?}", get_start_time());
+  if !auth_enabled() {
+    warn!("No API keys configured, authentication is disabled");
+  }
 
-  let server = HttpServer::new(|| {
+  let app_broker = broker.clone();
+  let server = HttpServer::new(move || {
     App::new()
-      .service(index_handler)
-      .service(server_info_handler)
-      .service(favicon_handler)
-      .service(create_queue_handler)
-      .service(list_queues_handler)
-      .service(get_queue_handler)
-      .service(enqueue_handler)
-      .service(ack_handler)
-      .service(peek_handler)
-      .service(dequeue_handler)
-      .service(edit_queue_handler)
-      .service(compact_handler)
-      .service(purge_handler)
-      .service(delete_handler)
-      .service(close_handler)
+      .data(app_broker.clone())
+      .wrap_fn(|req, srv| {
+        if is_shutting_down() && !is_health_check(req.path()) {
+          let res = HttpResponse::ServiceUnavailable()
+            .content_type("application/json")
+            .body(format_error(503, String::from("Server is shutting down")));
+          return Either::Left(ok(req.into_response(res)));
+        }
+        if !queues_loaded() && needs_queues(req.path()) {
+          let res = HttpResponse::ServiceUnavailable()
+            .content_type("application/json")
+            .body(format_error(
+              503,
+              String::from("Server is starting, queues are being loaded"),
+            ));
+          return Either::Left(ok(req.into_response(res)));
+        }
+        Either::Right(srv.call(req))
+      })
+      .wrap_fn(|req, srv| {
+        let res = match authorize(&req) {
+          Ok(()) => return Either::Right(srv.call(req)),
+          Err(AuthError::Unauthorized) => HttpResponse::Unauthorized()
+            .header("WWW-Authenticate", "Bearer")
+            .content_type("application/json")
+            .body(format_error(
+              401,
+              String::from("Missing or invalid API key"),
+            )),
+          Err(AuthError::Forbidden) => HttpResponse::Forbidden()
+            .content_type("application/json")
+            .body(format_error(
+              403,
+              String::from("API key is not allowed to access this route"),
+            )),
+        };
+        Either::Left(ok(req.into_response(res)))
+      })
+      .wrap_fn(|req, srv| {
+        let start = Instant::now();
+        let method = req.method().to_string();
+        let response = srv.call(req);
+        async move {
+          let res = response.await?;
+          let route = res.request().match_pattern();
+          observe_request(&method, route, res.status().as_u16(), start.elapsed());
+          Ok(res)
+        }
+      })
+      .wrap_fn(|req, srv| {
+        // Keep the caller's request id (e.g. from a proxy) so logs can be correlated
+        let request_id = req
+          .headers()
+          .get(REQUEST_ID_HEADER)
+          .and_then(|value| value.to_str().ok())
+          .filter(|value| !value.is_empty() && value.len() <= 128)
+          .map(String::from)
+          .unwrap_or_else(ulid_str);
+        let start = Instant::now();
+        let method = req.method().to_string();
+        let path = req.path().to_string();
+        let response = srv.call(req);
+        async move {
+          let mut res = response.await?;
+          let status = res.status().as_u16();
+          let duration_ms = start.elapsed().as_secs_f64() * 1000.0;
+          info!(
+            target: ACCESS_LOG_TARGET,
+            method = method.as_str(),
+            path = path.as_str(),
+            status = status,
+            duration_ms = duration_ms,
+            request_id = request_id.as_str();
+            "{} {} {}", method, path, status
+          );
+          if let Ok(value) = HeaderValue::from_str(&request_id) {
+            res
+              .headers_mut()
+              .insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+          }
+          Ok(res)
+        }
+      })
+      .configure(configure_app)
       .service(Files::new("/assets", "assets"))
       .service(Files::new("/dashboard", "dashboard"))
   });
 
-  server.bind(bind)?.run().await
+  let mut server = server
+    .disable_signals()
+    .shutdown_timeout(get_shutdown_timeout());
+  for address in get_listen_addresses() {
+    server = match &tls {
+      Some(config) => {
+        info!("Listening on {} (HTTPS)", address);
+        server.bind_rustls(&address, config.clone())?
+      }
+      None => {
+        info!("Listening on {}", address);
+        server.bind(&address)?
+      }
+    };
+  }
+  if let Some(address) = get_plain_address() {
+    info!("Listening on {} (plain HTTP)", address);
+    server = server.bind(address)?;
+  }
+  #[cfg(unix)]
+  if let Some(path) = get_unix_socket() {
+    if let Err(error) = remove_stale_socket(&path) {
+      error!("Cannot listen on unix:{}: {}", path, error);
+      process::exit(1);
+    }
+    info!("Listening on unix:{}", path);
+    server = server.bind_uds(&path)?;
+  }
+
+  let server = server.run();
+  register_server(server.clone());
+  rt::spawn(listen_for_signals());
+  rt::spawn(listen_for_reload());
+  // Health & server info are served while large queue logs are replayed
+  let loading_broker = broker.clone();
+  thread::spawn(move || load_queues(loading_broker));
+
+  // Returns after the server was stopped (signal or /close) and running requests finished
+  server.await?;
+
+  finish_shutdown(&broker);
+  if let Some(path) = get_unix_socket() {
+    std::fs::remove_file(path).ok();
+  }
+  Ok(())
 }
@@ -0,0 +1,227 @@
+use crate::global_data::get_start_time;
+use corinth::date::elapsed_secs;
+use corinth::{Broker, Queue};
+use lazy_static::lazy_static;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+// Upper bounds (in seconds) of the request latency histogram buckets
+const LATENCY_BUCKETS: [f64; 12] = [
+  0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0,
+];
+
+struct Histogram {
+  // Non-cumulative counts per bucket, the last one is +Inf
+  buckets: [u64; LATENCY_BUCKETS.len() + 1],
+  sum: f64,
+  count: u64,
+}
+
+impl Histogram {
+  fn new() -> Histogram {
+    Histogram {
+      buckets: [0; LATENCY_BUCKETS.len() + 1],
+      sum: 0.0,
+      count: 0,
+    }
+  }
+
+  fn observe(&mut self, secs: f64) {
+    let index = LATENCY_BUCKETS
+      .iter()
+      .position(|bound| secs <= *bound)
+      .unwrap_or(LATENCY_BUCKETS.len());
+    self.buckets[index] += 1;
+    self.sum += secs;
+    self.count += 1;
+  }
+}
+
+// Name, help text & value of a per-queue metric
+type QueueMetric = (&'static str, &'static str, fn(&Queue) -> u64);
+
+// Method, route pattern & status code
+type RouteKey = (String, String, u16);
+
+lazy_static! {
+  static ref REQUEST_LATENCIES: Mutex<BTreeMap<RouteKey, Histogram>> = Mutex::new(BTreeMap::new());
+}
+
+// Records the latency of a finished request
+// Routes are identified by their pattern (e.g. /queue/{queue_name}), so queue names do not create new series
+pub fn observe_request(method: &str, route: Option<String>, status: u16, duration: Duration) {
+  let route = route.unwrap_or_else(|| String::from("unmatched"));
+  REQUEST_LATENCIES
+    .lock()
+    .unwrap()
+    .entry((method.to_string(), route, status))
+    .or_insert_with(Histogram::new)
+    .observe(duration.as_secs_f64());
+}
+
+fn escape_label(value: &str) -> String {
+  value
+    .replace('\\', "\\\\")
+    .replace('"', "\\\"")
+    .replace('\n', "\\n")
+}
+
+fn write_header(out: &mut String, name: &str, metric_type: &str, help: &str) {
+  writeln!(out, "# HELP {} {}", name, help).unwrap();
+  writeln!(out, "# TYPE {} {}", name, metric_type).unwrap();
+}
+
+// Writes one sample per queue
+fn write_queue_metric(
+  out: &mut String,
+  queues: &[(&String, &Queue)],
+  name: &str,
+  metric_type: &str,
+  help: &str,
+  value: fn(&Queue) -> u64,
+) {
+  write_header(out, name, metric_type, help);
+  for (queue_name, queue) in queues {
+    writeln!(
+      out,
+      "{}{{queue=\"{}\"}} {}",
+      name,
+      escape_label(queue_name),
+      value(queue)
+    )
+    .unwrap();
+  }
+}
+
+fn write_queue_metrics(out: &mut String, broker: &Broker) {
+  let queue_map = broker.queues();
+  let mut queues: Vec<(&String, &Queue)> = queue_map.iter().collect();
+  queues.sort_by(|a, b| a.0.cmp(b.0));
+
+  write_header(out, "corinth_queues", "gauge", "Number of queues");
+  writeln!(out, "corinth_queues {}", queues.len()).unwrap();
+
+  let gauges: [QueueMetric; 6] = [
+    ("corinth_queue_size", "Messages waiting in the queue", |q| {
+      q.size() as u64
+    }),
+    (
+      "corinth_queue_in_flight",
+      "Dequeued messages waiting to be acknowledged",
+      |q| q.ack_size() as u64,
+    ),
+    (
+      "corinth_queue_deduplicating",
+      "Tracked deduplication IDs",
+      |q| q.dedup_size() as u64,
+    ),
+    (
+      "corinth_queue_oldest_message_age_seconds",
+      "Age of the oldest waiting message",
+      |q| q.oldest_message_age(),
+    ),
+    (
+      "corinth_queue_memory_bytes",
+      "Approximate memory usage of the queue",
+      |q| q.get_memory_size() as u64,
+    ),
+    (
+      "corinth_queue_disk_bytes",
+      "Size of the queue's files (0 for volatile queues)",
+      |q| q.get_disk_size().unwrap_or(0),
+    ),
+  ];
+  for (name, help, value) in gauges.iter() {
+    write_queue_metric(out, &queues, name, "gauge", help, *value);
+  }
+
+  let counters: [QueueMetric; 6] = [
+    (
+      "corinth_queue_enqueued_total",
+      "Messages enqueued (or imported)",
+      |q| q.num_enqueued(),
+    ),
+    ("corinth_queue_dequeued_total", "Messages dequeued", |q| {
+      q.num_dequeued()
+    }),
+    (
+      "corinth_queue_acknowledged_total",
+      "Messages acknowledged",
+      |q| q.num_acknowledged(),
+    ),
+    (
+      "corinth_queue_requeued_total",
+      "Unacknowledged messages put back into the queue",
+      |q| q.num_requeued(),
+    ),
+    (
+      "corinth_queue_deduplicated_total",
+      "Messages dropped because of their deduplication ID",
+      |q| q.num_deduplicated(),
+    ),
+    (
+      "corinth_queue_dead_lettered_total",
+      "Messages moved to the dead letter queue",
+      |q| q.num_dead_lettered(),
+    ),
+  ];
+  for (name, help, value) in counters.iter() {
+    write_queue_metric(out, &queues, name, "counter", help, *value);
+  }
+}
+
+fn write_request_metrics(out: &mut String) {
+  let name = "corinth_http_request_duration_seconds";
+  write_header(out, name, "histogram", "HTTP request latency by route");
+
+  for ((method, route, status), histogram) in REQUEST_LATENCIES.lock().unwrap().iter() {
+    let labels = format!(
+      "method=\"{}\",route=\"{}\",status=\"{}\"",
+      method,
+      escape_label(route),
+      status
+    );
+    let mut cumulative = 0;
+    for (bound, count) in LATENCY_BUCKETS.iter().zip(histogram.buckets.iter()) {
+      cumulative += count;
+      writeln!(
+        out,
+        "{}_bucket{{{},le=\"{}\"}} {}",
+        name, labels, bound, cumulative
+      )
+      .unwrap();
+    }
+    writeln!(
+      out,
+      "{}_bucket{{{},le=\"+Inf\"}} {}",
+      name, labels, histogram.count
+    )
+    .unwrap();
+    writeln!(out, "{}_sum{{{}}} {}", name, labels, histogram.sum).unwrap();
+    writeln!(out, "{}_count{{{}}} {}", name, labels, histogram.count).unwrap();
+  }
+}
+
+// Renders all metrics in the Prometheus text exposition format
+pub fn render_metrics(broker: &Broker) -> String {
+  let mut out = String::new();
+
+  write_header(
+    &mut out,
+    "corinth_uptime_seconds",
+    "gauge",
+    "Time since the server started",
+  );
+  writeln!(
+    out,
+    "corinth_uptime_seconds {}",
+    elapsed_secs(get_start_time())
+  )
+  .unwrap();
+
+  write_queue_metrics(&mut out, broker);
+  write_request_metrics(&mut out);
+  out
+}
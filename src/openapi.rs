@@ -0,0 +1,66 @@
+// OpenAPI document of the HTTP API, generated from the route handlers & the types of corinth-api
+use crate::routes;
+use utoipa::openapi::security::{Http, HttpAuthScheme, SecurityScheme};
+use utoipa::openapi::OpenApi as OpenApiDocument;
+use utoipa::{Modify, OpenApi};
+
+// Parts of the document the derive cannot express
+struct DocumentSettings;
+
+impl Modify for DocumentSettings {
+  fn modify(&self, openapi: &mut OpenApiDocument) {
+    // Taken from Cargo.toml, which declares none
+    openapi.info.license = None;
+    // API keys are sent as bearer tokens
+    let components = openapi.components.get_or_insert_with(Default::default);
+    components.add_security_scheme(
+      "api_key",
+      SecurityScheme::Http(Http::new(HttpAuthScheme::Bearer)),
+    );
+  }
+}
+
+#[derive(OpenApi)]
+#[openapi(
+  info(
+    title = "Corinth",
+    description = "Message queue with persistent & volatile queues.\n\nIf API keys are configured, requests need an `Authorization: Bearer <key>` header, except for the routes without security requirement. Missing or unknown keys are rejected with 401, keys without the required scope with 403."
+  ),
+  paths(
+    routes::server_info_handler,
+    routes::openapi_handler,
+    routes::list_queues_handler,
+    routes::get_queue_handler,
+    routes::create_queue_handler,
+    routes::edit_queue_handler,
+    routes::delete_handler,
+    routes::purge_handler,
+    routes::compact_handler,
+    routes::export_handler,
+    routes::import_handler,
+    routes::enqueue_handler,
+    routes::dequeue_handler,
+    routes::peek_handler,
+    routes::ack_handler,
+    routes::extend_handler,
+    routes::liveness_handler,
+    routes::readiness_handler,
+    routes::metrics_handler,
+    routes::snapshot_handler,
+    routes::close_handler,
+  ),
+  modifiers(&DocumentSettings),
+  security(("api_key" = [])),
+  tags(
+    (name = "Server", description = "Server info, health & administration"),
+    (name = "Queue", description = "Queue management"),
+    (name = "Message", description = "Producing & consuming messages"),
+  )
+)]
+pub struct ApiDoc;
+
+pub fn openapi_json() -> String {
+  ApiDoc::openapi()
+    .to_pretty_json()
+    .expect("JSON stringify failed")
+}
@@ -0,0 +1,185 @@
+use crate::config::{config, QueueDeclaration, QueueDefaults};
+use corinth::{Broker, Queue, QueueDeadLetterSettings, QueueSettings};
+use log::{info, warn};
+use std::fmt::Display;
+
+fn report_drift(queue_name: &str, setting: &str, actual: impl Display, declared: impl Display) {
+  warn!(
+    "Queue {}: {} is {}, but the config declares {}",
+    queue_name, setting, actual, declared
+  );
+}
+
+fn format_dead_letter(dead_letter_queue: Option<&QueueDeadLetterSettings>) -> String {
+  match dead_letter_queue {
+    Some(settings) => format!("{} (threshold {})", settings.name, settings.threshold),
+    None => String::from("none"),
+  }
+}
+
+// Applies the declared settings to an existing queue
+// Returns the amount of settings that differed from the config
+fn reconcile_queue(
+  queue: &mut Queue,
+  declaration: &QueueDeclaration,
+  defaults: &QueueDefaults,
+) -> usize {
+  let name = &declaration.name;
+  let mut num_drifted = 0;
+
+  if let Some(persistent) = declaration.persistent {
+    if persistent != queue.is_persistent() {
+      report_drift(name, "persistent", queue.is_persistent(), persistent);
+      warn!(
+        "Queue {}: persistence cannot be changed, delete the queue to recreate it",
+        name
+      );
+      num_drifted += 1;
+    }
+  }
+
+  if let Some(requeue_time) = declaration.requeue_time {
+    if requeue_time != queue.requeue_time() {
+      report_drift(name, "requeue_time", queue.requeue_time(), requeue_time);
+      queue.set_requeue_time(requeue_time);
+      num_drifted += 1;
+    }
+  }
+
+  if let Some(deduplication_time) = declaration.deduplication_time {
+    if deduplication_time != queue.deduplication_time() {
+      report_drift(
+        name,
+        "deduplication_time",
+        queue.deduplication_time(),
+        deduplication_time,
+      );
+      queue.set_deduplication_time(deduplication_time);
+      num_drifted += 1;
+    }
+  }
+
+  if let Some(max_length) = declaration.max_length {
+    if max_length != queue.max_length() {
+      report_drift(name, "max_length", queue.max_length(), max_length);
+      queue.set_max_length(max_length);
+      num_drifted += 1;
+    }
+  }
+
+  if let Some(target) = &declaration.dead_letter_queue {
+    // Without a declared threshold, the current one is kept
+    let threshold = declaration.dead_letter_threshold.unwrap_or_else(|| {
+      queue
+        .dead_letter_queue()
+        .map_or(defaults.dead_letter_threshold, |settings| {
+          settings.threshold
+        })
+    });
+    let declared = QueueDeadLetterSettings {
+      name: target.clone(),
+      threshold,
+    };
+    let matches = queue.dead_letter_queue().is_some_and(|settings| {
+      settings.name == declared.name && settings.threshold == declared.threshold
+    });
+    if !matches {
+      report_drift(
+        name,
+        "dead letter queue",
+        format_dead_letter(queue.dead_letter_queue()),
+        format_dead_letter(Some(&declared)),
+      );
+      queue.set_dead_letter_queue(Some(declared));
+      num_drifted += 1;
+    }
+  }
+
+  if num_drifted > 0 && queue.is_persistent() {
+    queue.write_metadata();
+  }
+  num_drifted
+}
+
+fn queue_settings(declaration: &QueueDeclaration, defaults: &QueueDefaults) -> QueueSettings {
+  let dead_letter_queue =
+    declaration
+      .dead_letter_queue
+      .as_ref()
+      .map(|target| QueueDeadLetterSettings {
+        name: target.clone(),
+        threshold: declaration
+          .dead_letter_threshold
+          .unwrap_or(defaults.dead_letter_threshold),
+      });
+
+  QueueSettings {
+    requeue_time: declaration.requeue_time.unwrap_or(defaults.requeue_time),
+    deduplication_time: declaration
+      .deduplication_time
+      .unwrap_or(defaults.deduplication_time),
+    persistent: declaration.persistent.unwrap_or(defaults.persistent),
+    max_length: declaration.max_length.unwrap_or(defaults.max_length),
+    dead_letter_queue,
+  }
+}
+
+// Creates the queues declared in the config and reconciles the settings of existing ones
+// Settings that differ from the config (drift) are reported and overwritten
+pub fn provision_queues(broker: &Broker) -> Result<(), String> {
+  let declarations = config().queues.clone();
+  let defaults = config().queue_defaults.clone();
+  if declarations.is_empty() {
+    return Ok(());
+  }
+
+  let mut queue_map = broker.queues();
+
+  for declaration in &declarations {
+    if let Some(target) = &declaration.dead_letter_queue {
+      let is_declared = declarations.iter().any(|other| &other.name == target);
+      if !is_declared && !queue_map.contains_key(target) {
+        return Err(format!(
+          "Dead letter queue {} of queue {} not found",
+          target, declaration.name
+        ));
+      }
+    }
+  }
+
+  let mut num_created = 0;
+  let mut num_reconciled = 0;
+
+  for declaration in &declarations {
+    match queue_map.get_mut(&declaration.name) {
+      Some(queue) => {
+        if reconcile_queue(queue, declaration, &defaults) > 0 {
+          num_reconciled += 1;
+        }
+      }
+      None => {
+        info!("Creating declared queue {}", declaration.name);
+        let settings = queue_settings(declaration, &defaults);
+        broker.insert_queue(&mut queue_map, &declaration.name, settings);
+        num_created += 1;
+      }
+    }
+  }
+
+  for name in queue_map.keys() {
+    if !declarations
+      .iter()
+      .any(|declaration| &declaration.name == name)
+    {
+      info!("Queue {} is not declared in the config", name);
+    }
+  }
+
+  info!(
+    "Provisioned {} declared queues ({} created, {} reconciled)",
+    declarations.len(),
+    num_created,
+    num_reconciled
+  );
+  Ok(())
+}
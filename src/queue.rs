@@ -1,39 +1,76 @@
-use crate::date::timestamp;
-use crate::env::data_folder;
-use crate::fs::{append_to_file, file_exists};
-use crate::global_data::QUEUES;
+use crate::broker::QueueMap;
+use crate::date::{after, schedule, timestamp};
+use crate::fs::{append_to_file, file_exists, sync_file, write_file_atomic};
+use corinth_api as api;
+pub use corinth_api::{MessageState, QueueDeadLetterSettings, QueueMeta};
+use log::{debug, error, info, trace, warn};
 use oysterpack_uid::ulid::ulid_str;
-use serde_json::json;
 use serde_json::Value;
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs::{create_dir_all, read_to_string, remove_dir_all, remove_file, rename, File};
-use std::io::{BufRead, BufReader, Write};
+use std::io::{BufRead, BufReader};
 use std::mem::size_of;
 use std::path::Path;
+use std::sync::{Mutex, Weak};
 use std::thread;
 use std::time::Duration;
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
-enum MessageState {
-  Pending,
-  Requeued,
-  Failed,
-}
-
 type StringifiedJson = String;
 
+// Field of the records marking the head of the queue as dequeued
+pub(crate) const DELETED_FLAG: &str = "$corinth_deleted";
+
 // Preallocate some space to avoid early reallocations
 const INITIAL_SIZE: usize = 256;
 
-pub fn unwrap_message(msg: Message) -> Value {
-  let content: Value = serde_json::from_str(&msg.item).unwrap();
-  json!({
-    "id": msg.id,
-    "queued_at": msg.queued_at,
-    "updated_at": msg.updated_at,
-    "item": content,
-    "state": msg.state,
-    "num_requeues": msg.num_requeues,
+// Version of the on-disk layout (meta.json & items file), bumped on incompatible changes
+// 1: timestamps in seconds, 2: timestamps in milliseconds
+pub const STORAGE_FORMAT_VERSION: u32 = 2;
+
+// 10^11 milliseconds is in 1973, 10^11 seconds in 5138, so smaller values are seconds
+const MAX_SECONDS_TIMESTAMP: u64 = 100_000_000_000;
+
+// Converts a seconds timestamp (storage format 1) to milliseconds
+// Values that are already in milliseconds are kept, so converting twice is harmless
+pub fn upgrade_timestamp(timestamp: u64) -> u64 {
+  if timestamp > 0 && timestamp < MAX_SECONDS_TIMESTAMP {
+    timestamp * 1000
+  } else {
+    timestamp
+  }
+}
+
+// Requeue times are fractional seconds, Duration::from_secs_f64 panics on other values
+pub fn is_valid_requeue_time(secs: f64) -> bool {
+  secs.is_finite() && secs >= 0.0 && secs <= f64::from(u32::MAX)
+}
+
+// Converts the message into the shape returned by the API
+pub fn unwrap_message(msg: Message) -> api::Message {
+  api::Message {
+    item: serde_json::from_str(&msg.item).unwrap(),
+    id: msg.id,
+    queued_at: msg.queued_at,
+    updated_at: msg.updated_at,
+    state: msg.state,
+    num_requeues: msg.num_requeues,
+  }
+}
+
+// Inverse of unwrap_message
+// Returns None if the message is not valid
+pub fn wrap_message(msg: api::Message) -> Option<Message> {
+  if msg.id.is_empty() || !msg.item.is_object() {
+    return None;
+  }
+  // Exports of older versions contain seconds
+  Some(Message {
+    id: msg.id,
+    queued_at: upgrade_timestamp(msg.queued_at),
+    updated_at: upgrade_timestamp(msg.updated_at),
+    item: serde_json::to_string(&msg.item).unwrap(),
+    state: msg.state,
+    num_requeues: msg.num_requeues,
   })
 }
 
@@ -47,33 +84,100 @@ pub struct Message {
   num_requeues: u16,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
-pub struct QueueDeadLetterSettings {
-  pub name: String,
-  pub threshold: u16,
+impl Message {
+  pub fn id(&self) -> &str {
+    &self.id
+  }
+
+  // Returns the message's JSON object
+  pub fn item(&self) -> Value {
+    serde_json::from_str(&self.item).unwrap()
+  }
+
+  pub fn queued_at(&self) -> u64 {
+    self.queued_at
+  }
+
+  pub fn num_requeues(&self) -> u16 {
+    self.num_requeues
+  }
+
+  fn upgrade_timestamps(&mut self) {
+    self.queued_at = upgrade_timestamp(self.queued_at);
+    self.updated_at = upgrade_timestamp(self.updated_at);
+  }
 }
 
-#[derive(Serialize, Deserialize, Clone)]
-pub struct QueueMeta {
-  created_at: u64,
-  last_compacted_at: u64,
-  num_acknowledged: u64,
-  num_deduplicated: u64,
-  num_requeued: u64,
-  requeue_time: u32,
-  deduplication_time: u32,
-  max_length: u64,
+// Settings of a new queue
+#[derive(Clone)]
+pub struct QueueSettings {
+  // In seconds, may be fractional
+  pub requeue_time: f64,
+  pub deduplication_time: u32,
+  pub persistent: bool,
+  pub max_length: u64,
   pub dead_letter_queue: Option<QueueDeadLetterSettings>,
 }
 
+impl Default for QueueSettings {
+  fn default() -> Self {
+    QueueSettings {
+      requeue_time: 300.0,
+      deduplication_time: 300,
+      persistent: true,
+      max_length: 0,
+      dead_letter_queue: None,
+    }
+  }
+}
+
+// Metadata of a new queue
+pub(crate) fn initial_meta(settings: &QueueSettings) -> QueueMeta {
+  QueueMeta {
+    num_requeued: 0,
+    num_deduplicated: 0,
+    num_acknowledged: 0,
+    created_at: timestamp(),
+    last_compacted_at: 0,
+    requeue_time: settings.requeue_time,
+    deduplication_time: settings.deduplication_time,
+    max_length: settings.max_length,
+    dead_letter_queue: settings.dead_letter_queue.clone(),
+    num_enqueued: 0,
+    num_dequeued: 0,
+    num_dead_lettered: 0,
+    format_version: STORAGE_FORMAT_VERSION,
+  }
+}
+
+// Metadata written by older storage formats
+fn is_legacy_meta(meta: &QueueMeta) -> bool {
+  meta.format_version < STORAGE_FORMAT_VERSION
+}
+
+// Converts metadata of older storage formats
+fn upgrade_meta(meta: &mut QueueMeta) {
+  meta.created_at = upgrade_timestamp(meta.created_at);
+  meta.last_compacted_at = upgrade_timestamp(meta.last_compacted_at);
+  meta.format_version = STORAGE_FORMAT_VERSION;
+}
+
 pub struct Queue {
   id: String,
+  // Folder the items & metadata are stored in (if persistent)
+  folder: String,
+  // Queues of the broker owning the queue, used by timers
+  owner: Weak<Mutex<QueueMap>>,
 
   items: VecDeque<Message>,
   dedup_set: HashSet<String>,
   ack_map: HashMap<String, Message>,
+  // Time (in milliseconds) unacknowledged messages are requeued at, by message id
+  leases: HashMap<String, u64>,
 
   meta: QueueMeta,
+  // Whether the counters changed since the metadata was last written
+  meta_dirty: bool,
 
   persistent: bool,
 }
@@ -82,29 +186,35 @@ fn path_to_string(path: std::path::PathBuf) -> String {
   path.into_os_string().into_string().expect("Path error")
 }
 
-// Returns the relative folder path in which
-// the queue is stored (items & metadata)
-pub fn get_queue_folder(id: &String) -> String {
-  let path = Path::new(&data_folder()).join("queues").join(&id);
+// Returns the folder in which a queue
+// of the given data folder is stored (items & metadata)
+pub fn get_queue_folder(data_folder: &str, id: &str) -> String {
+  let path = Path::new(data_folder).join("queues").join(id);
   path_to_string(path)
 }
 
-pub fn queue_meta_file(id: &String) -> String {
-  let path = Path::new(&get_queue_folder(id)).join("meta.json");
+pub fn queue_meta_file(folder: &str) -> String {
+  let path = Path::new(folder).join("meta.json");
   path_to_string(path)
 }
 
-// Returns the relative path to the persistent storage file
+// Previous version of the metadata file, used if meta.json is corrupted
+pub fn queue_meta_backup_file(folder: &str) -> String {
+  let path = Path::new(folder).join("meta.json.bak");
+  path_to_string(path)
+}
+
+// Returns the path to the persistent storage file
 // of the queue's items
-fn queue_item_file(id: &String, suffix: String) -> String {
+pub(crate) fn queue_item_file(folder: &str, suffix: &str) -> String {
   let filename = format!("items{}.jsonl", suffix);
-  let path = Path::new(&get_queue_folder(id)).join(filename);
+  let path = Path::new(folder).join(filename);
   path_to_string(path)
 }
 
 // Temp file to write into
-fn queue_temp_file(id: &String) -> String {
-  queue_item_file(&id, String::from("~"))
+pub(crate) fn queue_temp_file(folder: &str) -> String {
+  queue_item_file(folder, "~")
 }
 
 // Reads a file and returns the resulting queue
@@ -113,18 +223,27 @@ fn queue_temp_file(id: &String) -> String {
 fn read_file(file: &String) -> VecDeque<Message> {
   // Result queue
   let mut items: VecDeque<Message> = VecDeque::with_capacity(INITIAL_SIZE);
-  let deleted_flag = "$corinth_deleted";
+  let path = file;
 
   // Read file line-by-line
-  let file = File::open(&file).expect("Couldn't open items.jsonl");
+  let file = File::open(file).expect("Couldn't open items.jsonl");
   let reader = BufReader::new(file);
-  for line in reader.lines() {
-    let line = line.unwrap();
-    let obj: Value = serde_json::from_str(&line).expect("JSON parse failed");
-    if obj[deleted_flag].is_string() {
+  for (index, line) in reader.lines().enumerate() {
+    let corrupt = |error: String| -> ! {
+      panic!(
+        "Corrupt record in line {} of {} ({}), inspect & repair it with corinth-storage",
+        index + 1,
+        path,
+        error
+      )
+    };
+    let line = line.unwrap_or_else(|error| corrupt(error.to_string()));
+    let obj: Value = serde_json::from_str(&line).unwrap_or_else(|error| corrupt(error.to_string()));
+    if obj[DELETED_FLAG].is_string() {
       items.pop_front();
     } else {
-      let msg: Message = serde_json::from_str(&line).expect("JSON parse failed");
+      let msg: Message =
+        serde_json::from_str(&line).unwrap_or_else(|error| corrupt(error.to_string()));
       items.push_back(msg);
     }
   }
@@ -134,70 +253,190 @@ fn read_file(file: &String) -> VecDeque<Message> {
 
 // Write all items into a temp file
 // Then rename tmp_file ~> real_file
-fn compact_file(write_file: &String, compact_to: &String, items: &VecDeque<Message>) {
-  eprintln!("Compacting {}", compact_to);
+pub(crate) fn compact_file(write_file: &String, compact_to: &String, items: &VecDeque<Message>) {
+  info!("Compacting {}", compact_to);
 
   File::create(write_file).expect("Failed to create temporary write file");
 
   for item in items.iter() {
-    let line = serde_json::to_string(&item)
-      .ok()
-      .expect("JSON stringify error");
+    let line = serde_json::to_string(&item).expect("JSON stringify error");
     append_to_file(write_file, format!("{}\n", line));
   }
 
-  rename(write_file, &compact_to).expect("Failed to compact queue items");
+  rename(write_file, compact_to).expect("Failed to compact queue items");
 }
 
 // Initializes the queue's item queue from disk
-fn init_items(id: &String) -> VecDeque<Message> {
+// Items of older storage formats are converted, compaction then writes them in the current format
+fn init_items(folder: &str, legacy: bool) -> VecDeque<Message> {
   let mut items: VecDeque<Message> = VecDeque::with_capacity(INITIAL_SIZE);
 
-  let queue_item_file = queue_item_file(&id, String::from(""));
+  let queue_item_file = queue_item_file(folder, "");
   if file_exists(&queue_item_file) {
     items = read_file(&queue_item_file);
+    if legacy {
+      items.iter_mut().for_each(Message::upgrade_timestamps);
+    }
     // Minimize file size
-    compact_file(&queue_temp_file(&id), &queue_item_file, &items);
+    compact_file(&queue_temp_file(folder), &queue_item_file, &items);
   }
 
   items
 }
 
-fn write_metadata(id: &String, meta: &QueueMeta) {
-  let file = queue_meta_file(&id);
-  eprintln!("Writing metadata of {} to {}", id, file);
-  let mut writer = File::create(&file).expect("unable to create meta file");
-  writer
-    .write_all(serde_json::to_string(&meta).unwrap().as_bytes())
-    .expect("unable to write");
+pub(crate) fn write_metadata(folder: &str, meta: &QueueMeta) {
+  let file = queue_meta_file(folder);
+  debug!("Writing metadata to {}", file);
+  write_file_atomic(
+    &file,
+    &queue_meta_backup_file(folder),
+    serde_json::to_string(&meta).unwrap(),
+  );
 }
 
-fn get_message(queue: &String, msg_id: &String) -> Option<Message> {
-  let mut queue_map = QUEUES.lock().unwrap();
-  let this_queue = queue_map.get_mut(queue);
+pub(crate) fn parse_metadata_file(file: &String) -> Option<QueueMeta> {
+  let metadata = read_to_string(file).ok()?;
+  serde_json::from_str(&metadata).ok()
+}
 
-  if this_queue.is_some() {
-    let message = this_queue.unwrap().ack_map.remove(msg_id);
-    message
-  } else {
-    None
-  }
+// Reads the queue's metadata
+// Falls back to the backup file if meta.json is missing or corrupted
+fn read_metadata(folder: &str) -> Option<QueueMeta> {
+  let metadata = parse_metadata_file(&queue_meta_file(folder));
+  if metadata.is_some() {
+    return metadata;
+  }
+  warn!(
+    "Couldn't read metadata file in {}, trying backup file",
+    folder
+  );
+  let metadata = parse_metadata_file(&queue_meta_backup_file(folder));
+  if metadata.is_some() {
+    // Don't let the broken file replace the backup on the next write
+    remove_file(queue_meta_file(folder)).ok();
+  }
+  metadata
+}
+
+// Runs the task on the owning broker's queue map
+// Returns None if the broker was dropped in the meantime
+fn with_queues<R>(
+  owner: &Weak<Mutex<QueueMap>>,
+  task: impl FnOnce(&mut QueueMap) -> R,
+) -> Option<R> {
+  let queues = owner.upgrade()?;
+  let mut queue_map = queues.lock().unwrap();
+  Some(task(&mut queue_map))
 }
 
 fn get_disk_size(filename: &String) -> u64 {
   let metadata = std::fs::metadata(filename).ok();
 
   if metadata.is_none() {
-    eprintln!("Could not read queue file metadata");
+    debug!("Could not read queue file metadata of {}", filename);
     return 0;
   }
 
   metadata.unwrap().len()
 }
 
+// Requeues the message once its lease ran out
+// If the lease was extended in the meantime, waits for the rest of it
+fn schedule_lease_expiry(
+  owner: Weak<Mutex<QueueMap>>,
+  queue_id: String,
+  message_id: String,
+  delay: Duration,
+) {
+  schedule(delay, move || {
+    let weak_owner = owner.clone();
+    with_queues(&owner, |queue_map| {
+      let queue = match queue_map.get_mut(&queue_id) {
+        Some(queue) => queue,
+        None => return,
+      };
+      // Acknowledged (or already requeued by another timer)
+      let deadline = match queue.leases.get(&message_id) {
+        Some(deadline) => *deadline,
+        None => return,
+      };
+      let now = timestamp();
+      if deadline > now {
+        let remaining = Duration::from_millis(deadline - now);
+        schedule_lease_expiry(weak_owner, queue_id, message_id, remaining);
+        return;
+      }
+      queue.leases.remove(&message_id);
+      if let Some(message) = queue.ack_map.remove(&message_id) {
+        expire_message(queue_map, &queue_id, message);
+      }
+    });
+  });
+}
+
+// Moves an unacknowledged message whose requeue time passed back into its queue,
+// or into the dead letter queue once it was requeued too often
+fn expire_message(queue_map: &mut QueueMap, queue_id: &str, mut message: Message) {
+  let dead_letter_options = match queue_map.get(queue_id) {
+    Some(queue) => queue.get_meta().dead_letter_queue,
+    None => return,
+  };
+  if let Some(dead_letter_options) = dead_letter_options {
+    if message.num_requeues >= dead_letter_options.threshold {
+      // Move into dead letter queue
+      let dead_letter_queue = queue_map.get_mut(&dead_letter_options.name);
+      if let Some(dead_letter_queue) = dead_letter_queue {
+        message.state = MessageState::Failed;
+        message.updated_at = timestamp();
+        debug!(
+          "Message <{}> added to dead letter queue <{}>",
+          message.id, dead_letter_queue.id
+        );
+        dead_letter_queue.enqueue_message(message);
+        if let Some(queue) = queue_map.get_mut(queue_id) {
+          queue.meta.num_dead_lettered += 1;
+          queue.meta_dirty = true;
+        }
+        return;
+      } else {
+        error!("Dead letter queue <{}> not found", dead_letter_options.name);
+      }
+    }
+  }
+
+  // Requeue
+  let queue = queue_map.get_mut(queue_id).unwrap();
+  message.state = MessageState::Requeued;
+  message.updated_at = timestamp();
+  message.num_requeues += 1;
+  debug!(
+    "Requeuing: message <{}> (requeue #: {})",
+    message.id, message.num_requeues
+  );
+  queue.meta.num_requeued += 1;
+  queue.meta_dirty = true;
+  queue.enqueue_message(message);
+}
+
 impl Queue {
-  pub fn write_metadata(&self) {
-    write_metadata(&self.id, &self.meta)
+  pub fn write_metadata(&mut self) {
+    write_metadata(&self.folder, &self.meta);
+    self.meta_dirty = false;
+  }
+
+  // Writes the metadata only if the counters changed since the last write
+  pub fn flush_metadata(&mut self) {
+    if self.persistent && self.meta_dirty {
+      self.write_metadata();
+    }
+  }
+
+  // Writes pending metadata changes & syncs the item file to disk
+  pub fn sync_to_disk(&mut self) {
+    if self.persistent {
+      self.flush_metadata();
+      sync_file(&queue_item_file(&self.folder, ""));
+    }
   }
 
   pub fn get_memory_size(&self) -> usize {
@@ -209,10 +448,10 @@ impl Queue {
 
   pub fn get_disk_size(&self) -> Option<u64> {
     if self.is_persistent() {
-      let item_file = queue_item_file(&self.id, String::from(""));
+      let item_file = queue_item_file(&self.folder, "");
       let item_file_size = get_disk_size(&item_file);
 
-      let meta_file = queue_meta_file(&self.id);
+      let meta_file = queue_meta_file(&self.folder);
       let meta_file_size = get_disk_size(&meta_file);
 
       Some(item_file_size + meta_file_size)
@@ -221,110 +460,166 @@ impl Queue {
     }
   }
 
-  pub fn start_compact_interval(&mut self, secs: u64) {
+  pub(crate) fn start_compact_interval(&mut self, secs: u64) {
     if !self.is_persistent() || secs == 0 {
       return;
     }
-    eprintln!(
-      "Starting compaction timer for {} ({} millis)",
+    debug!(
+      "Starting compaction timer for {} ({} secs)",
       self.get_name(),
       secs
     );
     let this_id = self.id.clone();
+    let owner = self.owner.clone();
     thread::spawn(move || loop {
-      thread::sleep(Duration::from_secs(secs));
-      let mut queue_map = QUEUES.lock().unwrap();
-      let this_queue = queue_map.get_mut(&this_id);
-      if this_queue.is_some() {
-        let queue = this_queue.unwrap();
-        queue.compact();
-      } else {
+      let queue_exists = after(Duration::from_secs(secs), || {
+        with_queues(&owner, |queue_map| {
+          let this_queue = queue_map.get_mut(&this_id);
+          this_queue.map(|queue| queue.compact()).is_some()
+        })
+        .unwrap_or(false)
+      });
+      if !queue_exists {
+        break;
+      }
+    });
+  }
+
+  // Counters (acks, dedup hits, requeues) are only kept in memory
+  // and flushed to disk periodically, instead of rewriting meta.json on every event
+  pub(crate) fn start_metadata_flush_interval(&mut self, secs: u64) {
+    if !self.is_persistent() || secs == 0 {
+      return;
+    }
+    debug!(
+      "Starting metadata flush timer for {} ({} secs)",
+      self.get_name(),
+      secs
+    );
+    let this_id = self.id.clone();
+    let owner = self.owner.clone();
+    thread::spawn(move || loop {
+      let queue_exists = after(Duration::from_secs(secs), || {
+        with_queues(&owner, |queue_map| {
+          let this_queue = queue_map.get_mut(&this_id);
+          this_queue.map(|queue| queue.flush_metadata()).is_some()
+        })
+        .unwrap_or(false)
+      });
+      if !queue_exists {
         break;
       }
     });
   }
 
   pub fn compact(&mut self) {
-    eprintln!("Compacting queue {}", &self.id);
+    info!("Compacting queue {}", &self.id);
 
-    let id = &self.id;
-    let queue_item_file = queue_item_file(&id, String::from(""));
-    compact_file(&queue_temp_file(id), &queue_item_file, &self.items);
+    let queue_item_file = queue_item_file(&self.folder, "");
+    compact_file(
+      &queue_temp_file(&self.folder),
+      &queue_item_file,
+      &self.items,
+    );
 
-    eprintln!("Updating last_compacted_at timestamp");
+    debug!("Updating last_compacted_at timestamp");
     self.meta.last_compacted_at = timestamp();
     self.write_metadata();
   }
 
   // Read queue from disk
-  pub fn from_disk(id: String) -> Queue {
-    eprintln!("Reading queue '{}' from disk", id);
-
-    let items: VecDeque<Message> = init_items(&id);
+  // Returns None if the queue's metadata cannot be recovered
+  pub(crate) fn from_disk(
+    id: String,
+    folder: String,
+    owner: Weak<Mutex<QueueMap>>,
+  ) -> Option<Queue> {
+    info!("Reading queue '{}' from disk", id);
+
+    let mut meta = read_metadata(&folder)?;
+    let legacy = is_legacy_meta(&meta);
+    // The items are rewritten before the metadata, so an interrupted upgrade is repeated on the next start
+    let items: VecDeque<Message> = init_items(&folder, legacy);
+    if legacy {
+      info!(
+        "Upgrading queue '{}' to storage format {}",
+        id, STORAGE_FORMAT_VERSION
+      );
+      upgrade_meta(&mut meta);
+    }
     let mut queue = Queue {
-      id: id.clone(),
+      id,
+      folder,
+      owner,
       items,
       dedup_set: HashSet::with_capacity(INITIAL_SIZE),
       ack_map: HashMap::with_capacity(INITIAL_SIZE),
-      meta: QueueMeta {
-        num_requeued: 0,
-        num_deduplicated: 0,
-        num_acknowledged: 0,
-        created_at: timestamp(),
-        last_compacted_at: timestamp(),
-        requeue_time: 300,
-        deduplication_time: 300,
-        max_length: 0,
-        dead_letter_queue: None,
-      },
+      leases: HashMap::with_capacity(INITIAL_SIZE),
+      meta,
+      meta_dirty: false,
       persistent: true,
     };
-    let metadata_file = queue_meta_file(&id);
-    let metadata = read_to_string(metadata_file).expect("Couldn't read metadata file");
-    let metadata: QueueMeta = serde_json::from_str(&metadata).expect("Couldn't read metadata file");
-    queue.meta = metadata;
 
     // Overwrite timestamp because it was compacted on init
     queue.meta.last_compacted_at = timestamp();
     queue.write_metadata();
 
-    queue
+    Some(queue)
+  }
+
+  // Recreate a volatile queue from a previous server run
+  pub(crate) fn from_volatile_snapshot(
+    id: String,
+    folder: String,
+    owner: Weak<Mutex<QueueMap>>,
+    mut meta: QueueMeta,
+    items: Vec<Message>,
+  ) -> Queue {
+    let mut items: VecDeque<Message> = items.into_iter().collect();
+    if is_legacy_meta(&meta) {
+      items.iter_mut().for_each(Message::upgrade_timestamps);
+      upgrade_meta(&mut meta);
+    }
+    Queue {
+      id,
+      folder,
+      owner,
+      items,
+      dedup_set: HashSet::with_capacity(INITIAL_SIZE),
+      ack_map: HashMap::with_capacity(INITIAL_SIZE),
+      leases: HashMap::with_capacity(INITIAL_SIZE),
+      meta,
+      meta_dirty: false,
+      persistent: false,
+    }
   }
 
   // Create a new empty queue
-  pub fn new(
+  pub(crate) fn new(
     id: String,
-    requeue_time: u32,
-    deduplication_time: u32,
-    persistent: bool,
-    max_length: u64,
-    dead_letter_queue: Option<QueueDeadLetterSettings>,
+    folder: String,
+    owner: Weak<Mutex<QueueMap>>,
+    settings: QueueSettings,
   ) -> Queue {
     let items: VecDeque<Message> = VecDeque::new();
-    let meta = QueueMeta {
-      num_requeued: 0,
-      num_deduplicated: 0,
-      num_acknowledged: 0,
-      created_at: timestamp(),
-      last_compacted_at: 0,
-      requeue_time,
-      deduplication_time,
-      max_length,
-      dead_letter_queue,
-    };
-    if persistent {
-      create_dir_all(get_queue_folder(&id)).expect("Invalid folder name");
-      eprintln!("Created queue folder");
-      write_metadata(&id, &meta);
+    let meta = initial_meta(&settings);
+    if settings.persistent {
+      create_dir_all(&folder).expect("Invalid folder name");
+      debug!("Created queue folder {}", folder);
+      write_metadata(&folder, &meta);
     }
-    return Queue {
+    Queue {
       id,
+      folder,
+      owner,
       items,
       dedup_set: HashSet::with_capacity(INITIAL_SIZE),
       ack_map: HashMap::with_capacity(INITIAL_SIZE),
+      leases: HashMap::with_capacity(INITIAL_SIZE),
       meta,
-      persistent,
-    };
+      meta_dirty: false,
+      persistent: settings.persistent,
+    }
   }
 
   // Acknowledge message reception
@@ -334,10 +629,9 @@ impl Queue {
     let item = self.ack_map.get(&id);
     if item.is_some() {
       self.ack_map.remove(&id);
+      self.leases.remove(&id);
       self.meta.num_acknowledged += 1;
-      if self.persistent {
-        self.write_metadata();
-      }
+      self.meta_dirty = true;
       true
     } else {
       false
@@ -347,13 +641,13 @@ impl Queue {
   // Start timeout thread to remove item from dedup map
   fn schedule_dedup_item(&mut self, id: String, lifetime: u64) {
     let this_id = self.id.clone();
-    thread::spawn(move || {
-      thread::sleep(Duration::from_secs(lifetime));
-      let mut queue_map = QUEUES.lock().unwrap();
-      let this_queue = queue_map.get_mut(&this_id);
-      if this_queue.is_some() {
-        this_queue.unwrap().dedup_set.remove(&id);
-      }
+    let owner = self.owner.clone();
+    schedule(Duration::from_secs(lifetime), move || {
+      with_queues(&owner, |queue_map| {
+        if let Some(this_queue) = queue_map.get_mut(&this_id) {
+          this_queue.dedup_set.remove(&id);
+        }
+      });
     });
   }
 
@@ -361,14 +655,11 @@ impl Queue {
   // If not, it will be tracked
   // Returns true if the id was not originally tracked, false otherwise
   fn register_dedup_id(&mut self, dedup_id: Option<String>) -> bool {
-    if dedup_id.is_some() {
-      let d_id = dedup_id.unwrap();
+    if let Some(d_id) = dedup_id {
       let dedup_in_map = self.dedup_set.contains(&d_id);
       if dedup_in_map {
         self.meta.num_deduplicated += 1;
-        if self.persistent {
-          self.write_metadata();
-        }
+        self.meta_dirty = true;
         return false;
       }
       let lifetime = self.meta.deduplication_time.into();
@@ -383,17 +674,52 @@ impl Queue {
   fn enqueue_message(&mut self, msg: Message) -> Message {
     self.items.push_back(msg.clone());
     if self.persistent {
-      let line = serde_json::to_string(&msg)
-        .ok()
-        .expect("JSON stringify error");
-      append_to_file(
-        &queue_item_file(&self.id, String::from("")),
-        format!("{}\n", line),
-      );
+      let line = serde_json::to_string(&msg).expect("JSON stringify error");
+      append_to_file(&queue_item_file(&self.folder, ""), format!("{}\n", line));
     }
     msg
   }
 
+  // Drops messages whose id is already in the queue (or earlier in the list)
+  pub fn filter_new_messages(&self, messages: Vec<Message>) -> Vec<Message> {
+    let mut ids: HashSet<String> = self
+      .items
+      .iter()
+      .chain(self.ack_map.values())
+      .map(|msg| msg.id.clone())
+      .collect();
+
+    messages
+      .into_iter()
+      .filter(|msg| {
+        let is_new = ids.insert(msg.id.clone());
+        if !is_new {
+          debug!(
+            "Message <{}> already in queue {}, skipping...",
+            msg.id, self.id
+          );
+        }
+        is_new
+      })
+      .collect()
+  }
+
+  // Appends previously exported messages, keeping their ids & timestamps
+  // Messages whose id is already in the queue are skipped
+  // Returns the amount of imported messages
+  pub fn import_messages(&mut self, messages: Vec<Message>) -> usize {
+    let messages = self.filter_new_messages(messages);
+    let num_imported = messages.len();
+
+    for msg in messages {
+      self.enqueue_message(msg);
+      self.meta.num_enqueued += 1;
+      self.meta_dirty = true;
+    }
+
+    num_imported
+  }
+
   fn enqueue_item(&mut self, id: String, item: Value) -> Message {
     let now = timestamp();
     let message = Message {
@@ -404,6 +730,8 @@ impl Queue {
       state: MessageState::Pending,
       num_requeues: 0,
     };
+    self.meta.num_enqueued += 1;
+    self.meta_dirty = true;
     self.enqueue_message(message)
   }
 
@@ -419,90 +747,63 @@ impl Queue {
   }
 
   // Start timeout thread to remove item from ack map & back into queue
-  fn schedule_ack_item(&mut self, message: Message, lifetime: u64) {
-    eprintln!(
-      "Schedule ack expiration: message <{}> (lifetime: {} secs)",
-      message.id, lifetime
+  fn schedule_ack_item(&mut self, message: Message, lifetime: Duration) {
+    trace!(
+      "Schedule ack expiration: message <{}> (lifetime: {:?})",
+      message.id,
+      lifetime
     );
     let message_id = message.id.clone();
-    self.ack_map.insert(message_id.clone(), message.clone());
-    let this_id = self.id.clone();
-    thread::spawn(move || {
-      thread::sleep(Duration::from_secs(lifetime));
-      let message = get_message(&this_id, &message_id);
-      if message.is_some() {
-        let mut queue_map = QUEUES.lock().unwrap();
-        let mut new_message = message.unwrap().clone();
-
-        let queue = queue_map.get(&this_id).unwrap();
-
-        let dead_letter_options = queue.get_meta().dead_letter_queue;
-        if dead_letter_options.is_some() {
-          if new_message.num_requeues >= dead_letter_options.clone().unwrap().threshold {
-            // Move into dead letter queue
-            let dead_letter_queue = queue_map.get_mut(&dead_letter_options.clone().unwrap().name);
-            if dead_letter_queue.is_some() {
-              let dead_letter_queue = dead_letter_queue.unwrap();
-              new_message.state = MessageState::Failed;
-              new_message.updated_at = timestamp();
-              eprintln!(
-                "Message <{}> added to dead letter queue <{}>",
-                new_message.id, dead_letter_queue.id
-              );
-              dead_letter_queue.enqueue_message(new_message);
-              return;
-            } else {
-              eprintln!(
-                "Dead letter queue <{}> not found",
-                dead_letter_options.unwrap().name
-              );
-            }
-          }
-        }
-
-        // Requeue
-        let queue = queue_map.get_mut(&this_id).unwrap();
-        new_message.state = MessageState::Requeued;
-        new_message.updated_at = timestamp();
-        new_message.num_requeues += 1;
-        eprintln!(
-          "Requeuing: message <{}> (requeue #: {})",
-          new_message.id, new_message.num_requeues
-        );
-        queue.meta.num_requeued += 1;
-        queue.enqueue_message(new_message);
-      }
-    });
+    self.ack_map.insert(message_id.clone(), message);
+    self.leases.insert(
+      message_id.clone(),
+      timestamp() + lifetime.as_millis() as u64,
+    );
+    schedule_lease_expiry(self.owner.clone(), self.id.clone(), message_id, lifetime);
+  }
+
+  // Moves the time an unacknowledged message is requeued at to now + lifetime
+  // Returns the new requeue time, or None if the message is not waiting to be acknowledged
+  pub fn extend_lease(&mut self, message_id: &str, lifetime: Duration) -> Option<u64> {
+    let previous = *self.leases.get(message_id)?;
+    let deadline = timestamp() + lifetime.as_millis() as u64;
+    self.leases.insert(String::from(message_id), deadline);
+    // Later deadlines are picked up by the running timer, earlier ones need their own
+    if deadline < previous {
+      schedule_lease_expiry(
+        self.owner.clone(),
+        self.id.clone(),
+        String::from(message_id),
+        lifetime,
+      );
+    }
+    trace!("Extended lease of message <{}> to {}", message_id, deadline);
+    Some(deadline)
   }
 
   // Returns the first element, but does not dequeue it
   pub fn peek(&self) -> Option<Message> {
-    let item_maybe = self.items.front();
-    if item_maybe.is_some() {
-      return Some(item_maybe.unwrap().clone());
-    }
-    return None;
+    self.items.front().cloned()
   }
 
   // Removes and returns the first element
   pub fn dequeue(&mut self, auto_ack: bool) -> Option<Message> {
     let item_maybe = self.peek();
-    if item_maybe.is_some() {
+    if let Some(message) = item_maybe.clone() {
       self.items.pop_front();
+      self.meta.num_dequeued += 1;
+      self.meta_dirty = true;
       if self.persistent {
-        let id = item_maybe.clone().unwrap().id;
-        let line = format!("{{\"$corinth_deleted\":\"{}\" }}\n", id);
-        append_to_file(&queue_item_file(&self.id, String::from("")), line);
+        let id = &message.id;
+        let line = format!("{{\"{}\":\"{}\" }}\n", DELETED_FLAG, id);
+        append_to_file(&queue_item_file(&self.folder, ""), line);
       }
       if auto_ack {
         self.meta.num_acknowledged += 1;
-        if self.persistent {
-          self.write_metadata();
-        }
+        self.meta_dirty = true;
       } else {
-        let message = item_maybe.clone().unwrap();
-        let lifetime = self.meta.requeue_time.into();
-        if lifetime > 0 {
+        let lifetime = Duration::try_from_secs_f64(self.meta.requeue_time).unwrap_or_default();
+        if lifetime > Duration::ZERO {
           self.schedule_ack_item(message, lifetime);
         }
       }
@@ -524,6 +825,18 @@ impl Queue {
     self.meta.clone()
   }
 
+  // Returns the queued messages, in queue order
+  pub fn items(&self) -> &VecDeque<Message> {
+    &self.items
+  }
+
+  // Returns the messages currently waiting to be acknowledged, oldest first
+  pub fn unacked_items(&self) -> Vec<&Message> {
+    let mut items: Vec<&Message> = self.ack_map.values().collect();
+    items.sort_by(|a, b| a.id.cmp(&b.id));
+    items
+  }
+
   pub fn max_length(&self) -> u64 {
     self.meta.max_length
   }
@@ -567,15 +880,27 @@ impl Queue {
     self.meta.deduplication_time = time;
   }
 
-  pub fn set_requeue_time(&mut self, time: u32) {
+  pub fn set_requeue_time(&mut self, time: f64) {
     self.meta.requeue_time = time;
   }
 
+  pub fn set_max_length(&mut self, max_length: u64) {
+    self.meta.max_length = max_length;
+  }
+
+  pub fn set_dead_letter_queue(&mut self, dead_letter_queue: Option<QueueDeadLetterSettings>) {
+    self.meta.dead_letter_queue = dead_letter_queue;
+  }
+
+  pub fn dead_letter_queue(&self) -> Option<&QueueDeadLetterSettings> {
+    self.meta.dead_letter_queue.as_ref()
+  }
+
   pub fn deduplication_time(&self) -> u32 {
     self.meta.deduplication_time
   }
 
-  pub fn requeue_time(&self) -> u32 {
+  pub fn requeue_time(&self) -> f64 {
     self.meta.requeue_time
   }
 
@@ -583,6 +908,26 @@ impl Queue {
     self.persistent
   }
 
+  pub fn num_enqueued(&self) -> u64 {
+    self.meta.num_enqueued
+  }
+
+  pub fn num_dequeued(&self) -> u64 {
+    self.meta.num_dequeued
+  }
+
+  pub fn num_dead_lettered(&self) -> u64 {
+    self.meta.num_dead_lettered
+  }
+
+  // Seconds the first queued message has been waiting, 0 if the queue is empty
+  pub fn oldest_message_age(&self) -> u64 {
+    self
+      .items
+      .front()
+      .map_or(0, |msg| timestamp().saturating_sub(msg.queued_at) / 1000)
+  }
+
   pub fn num_requeued(&self) -> u64 {
     self.meta.num_requeued
   }
@@ -594,19 +939,23 @@ impl Queue {
   pub fn purge(&mut self, delete: bool) {
     self.items = VecDeque::with_capacity(INITIAL_SIZE);
     self.ack_map = HashMap::with_capacity(INITIAL_SIZE);
+    self.leases = HashMap::with_capacity(INITIAL_SIZE);
     self.dedup_set = HashSet::with_capacity(INITIAL_SIZE);
+    // All counters start over, Prometheus handles this as a counter reset
+    self.meta.num_enqueued = 0;
+    self.meta.num_dequeued = 0;
     self.meta.num_acknowledged = 0;
     self.meta.num_deduplicated = 0;
     self.meta.num_requeued = 0;
+    self.meta.num_dead_lettered = 0;
 
     if self.persistent {
       if delete {
-        let folder = get_queue_folder(&self.id);
-        if Path::new(&folder).exists() {
-          remove_dir_all(folder).expect("Failed to delete queue folder");
+        if Path::new(&self.folder).exists() {
+          remove_dir_all(&self.folder).expect("Failed to delete queue folder");
         }
       } else {
-        let item_file = queue_item_file(&self.id, String::from(""));
+        let item_file = queue_item_file(&self.folder, "");
         if Path::new(&item_file).exists() {
           remove_file(item_file).expect("Failed to delete item file");
         }
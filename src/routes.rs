@@ -1,16 +1,73 @@
-use crate::date::{elapsed_secs, timestamp};
-use crate::env::get_compaction_interval;
+use crate::auth::{can_access_queue, can_see_queue, Operation};
+use crate::build_info::{build_timestamp, GIT_COMMIT, VERSION};
+use crate::config::config;
+use crate::env::data_folder;
 use crate::global_data::get_start_time;
-use crate::global_data::{queue_exists, QUEUES};
-use crate::queue::{unwrap_message, Message, Queue, QueueDeadLetterSettings};
+use crate::health::check_readiness;
+use crate::metrics::render_metrics;
+use crate::openapi::openapi_json;
 use crate::response::{format_error, format_success};
+use crate::shutdown::begin_shutdown;
+use crate::snapshot::create_snapshot;
 use actix_files::NamedFile;
+use actix_web::http::StatusCode;
+use actix_web::web::{Bytes, BytesMut};
 use actix_web::{delete, get, patch, post, put, web, HttpRequest, HttpResponse};
+#[cfg(debug_assertions)]
+use corinth::date::advance_clock;
+use corinth::date::{elapsed_ms, timestamp};
+use corinth::queue::{
+  is_valid_requeue_time, unwrap_message, wrap_message, Message, Queue, STORAGE_FORMAT_VERSION,
+};
+use corinth::{Broker, BrokerError, QueueDeadLetterSettings, QueueSettings};
+use corinth_api::{
+  BuildInfo, CreateQueueOptions, DequeueResult, EmptyResponse, EnqueueBody, EnqueueResult,
+  ErrorResponse, ExtendResult, ImportResult, InfoResult, Message as ApiMessage, NewMessage,
+  PeekResult, QueueInfo, QueueListResult, QueuePatchBody, QueueResult, ReadinessResult, Response,
+  ServerInfo, StorageInfo,
+};
+use futures::{stream, StreamExt};
+use log::info;
 use qstring::QString;
-use serde_json::{json, Value};
-use std::thread;
+#[cfg(debug_assertions)]
+use serde_derive::Deserialize;
+use serde_json::json;
 use std::time::Duration;
 
+const QUEUE_FORBIDDEN: &str = "API key is not allowed to access this queue";
+
+fn queue_forbidden() -> HttpResponse {
+  HttpResponse::Forbidden()
+    .content_type("application/json")
+    .body(format_error(403, String::from(QUEUE_FORBIDDEN)))
+}
+
+// Responds with 403 if the request's API key may not perform the operation on the queue of the route
+fn check_queue_access(req: &HttpRequest, operation: Operation) -> Result<(), HttpResponse> {
+  if can_access_queue(req, req.match_info().query("queue_name"), operation) {
+    Ok(())
+  } else {
+    Err(queue_forbidden())
+  }
+}
+
+fn queue_exists(broker: &Broker, req: &HttpRequest) -> bool {
+  broker.queue_exists(req.match_info().query("queue_name"))
+}
+
+fn broker_error_response(error: BrokerError) -> HttpResponse {
+  let status = match error {
+    BrokerError::QueueNotFound
+    | BrokerError::DeadLetterTargetNotFound
+    | BrokerError::MessageNotFound => StatusCode::NOT_FOUND,
+    BrokerError::QueueAlreadyExists => StatusCode::CONFLICT,
+    BrokerError::QueueFull | BrokerError::DeadLetterQueueInUse => StatusCode::FORBIDDEN,
+  };
+  HttpResponse::build(status)
+    .content_type("application/json")
+    .body(format_error(status.as_u16(), error.to_string()))
+}
+
 #[get("/dashboard")]
 async fn index_handler() -> actix_web::Result<NamedFile> {
   Ok(NamedFile::open("dashboard/index.html")?)
@@ -21,74 +78,135 @@ async fn favicon_handler() -> actix_web::Result<NamedFile> {
   Ok(NamedFile::open("assets/favicon.png")?)
 }
 
-#[allow(unused_doc_comments)]
-/**
- * @api {get} / Get server info
- * @apiName RootInfo
- * @apiGroup Server
- *
- * @apiSuccess {String} result:info:name Server name
- * @apiSuccess {String} result:info:version Server version
- * @apiSuccess {Number} result:info:uptime_ms Uptime in milliseconds
- * @apiSuccess {Number} result:info:uptime_secs Uptime in seconds
- * @apiSuccess {Number} result:info:started_at Unix timestamp when the server was started
- */
+// API docs viewer, renders /openapi.json
+#[get("/docs")]
+async fn docs_handler() -> HttpResponse {
+  HttpResponse::Ok()
+    .content_type("text/html; charset=utf-8")
+    .body(include_str!("../docs/api/index.html"))
+}
+
+// Vendored swagger-ui files of the docs viewer
+#[get("/swagger-ui/{file}")]
+async fn swagger_ui_handler(req: HttpRequest) -> HttpResponse {
+  let (content_type, body): (&str, &'static [u8]) = match req.match_info().query("file") {
+    "swagger-ui-bundle.js" => (
+      "application/javascript",
+      include_bytes!("../docs/api/swagger-ui/swagger-ui-bundle.js"),
+    ),
+    "swagger-ui.css" => (
+      "text/css",
+      include_bytes!("../docs/api/swagger-ui/swagger-ui.css"),
+    ),
+    _ => {
+      return HttpResponse::NotFound()
+        .content_type("application/json")
+        .body(format_error(404, String::from("File not found")))
+    }
+  };
+  HttpResponse::Ok()
+    .content_type(content_type)
+    .header("Cache-Control", "public, max-age=86400")
+    .body(body)
+}
+
+/// Get OpenAPI document
+///
+/// OpenAPI 3 description of this API
+#[utoipa::path(
+  get,
+  path = "/openapi.json",
+  tag = "Server",
+  responses(
+    (status = 200, description = "OpenAPI document", body = Object),
+  ),
+  security(()),
+)]
+#[get("/openapi.json")]
+async fn openapi_handler() -> HttpResponse {
+  HttpResponse::Ok()
+    .content_type("application/json")
+    .body(openapi_json())
+}
+
+/// Get server info
+#[utoipa::path(
+  get,
+  path = "/",
+  tag = "Server",
+  responses(
+    (status = 200, description = "Server info", body = Response<InfoResult>),
+  ),
+  security(()),
+)]
 #[get("/")]
 async fn server_info_handler() -> HttpResponse {
   let now = timestamp();
-  let uptime_secs = elapsed_secs(get_start_time());
-  let info = json!({
-    "info": {
-      "name": String::from("Corinth"),
-      "version": String::from("0.4.0"),
-      "uptime_ms": uptime_secs * 1000,
-      "uptime_secs": uptime_secs,
-      "started_at": now - uptime_secs,
-    }
-  });
+  let uptime_ms = elapsed_ms(get_start_time());
+  let info = ServerInfo {
+    name: String::from("Corinth"),
+    version: String::from(VERSION),
+    uptime_ms,
+    uptime_secs: uptime_ms / 1000,
+    started_at: now - uptime_ms,
+    build: BuildInfo {
+      commit: String::from(GIT_COMMIT),
+      built_at: build_timestamp(),
+    },
+    storage: StorageInfo {
+      format_version: STORAGE_FORMAT_VERSION,
+      data_folder: data_folder(),
+    },
+  };
   HttpResponse::Ok()
     .content_type("application/json")
     .body(format_success(
       200,
       String::from("Server info retrieved successfully"),
-      info,
+      json!({ "info": info }),
     ))
 }
 
-fn format_queue_info(queue: &Queue) -> Value {
-  json!({
-    "name": queue.get_name(),
-    "created_at": queue.created_at(),
-    "last_compacted_at": queue.last_compacted_at(),
-    "size": queue.size(),
-    "num_deduplicating": queue.dedup_size(),
-    "num_unacknowledged": queue.ack_size(),
-    "num_acknowledged": queue.num_acknowledged(),
-    "num_deduplicated": queue.num_deduplicated(),
-    "deduplication_time": queue.deduplication_time(),
-    "requeue_time": queue.requeue_time(),
-    "max_length": queue.max_length(),
-    "persistent": queue.is_persistent(),
-    "memory_size": queue.get_memory_size(),
-    "disk_size": queue.get_disk_size(),
-    "num_requeued": queue.num_requeued(),
-    "dead_letter": queue.get_meta().dead_letter_queue
-  })
-}
-
-#[allow(unused_doc_comments)]
-/**
- * @api {get} /queues List queues
- * @apiName ListQueues
- * @apiGroup Queue
- *
- * @apiSuccess {String} result:queues:items Queue names
- * @apiSuccess {Number} result:queues:length Amount of queues
- */
+fn format_queue_info(queue: &Queue) -> QueueInfo {
+  QueueInfo {
+    name: queue.get_name(),
+    created_at: queue.created_at(),
+    last_compacted_at: queue.last_compacted_at(),
+    size: queue.size(),
+    num_deduplicating: queue.dedup_size(),
+    num_unacknowledged: queue.ack_size(),
+    num_acknowledged: queue.num_acknowledged(),
+    num_deduplicated: queue.num_deduplicated(),
+    deduplication_time: queue.deduplication_time(),
+    requeue_time: queue.requeue_time(),
+    max_length: queue.max_length(),
+    persistent: queue.is_persistent(),
+    memory_size: queue.get_memory_size(),
+    disk_size: queue.get_disk_size(),
+    num_requeued: queue.num_requeued(),
+    dead_letter: queue.get_meta().dead_letter_queue,
+  }
+}
+
+/// List queues
+///
+/// Only lists the queues the API key may see
+#[utoipa::path(
+  get,
+  path = "/queues",
+  tag = "Queue",
+  responses(
+    (status = 200, description = "Queue list", body = Response<QueueListResult>),
+  ),
+)]
 #[get("/queues")]
-async fn list_queues_handler() -> HttpResponse {
-  let queue_map = QUEUES.lock().unwrap();
-  let queue_info: Vec<Value> = queue_map.values().map(format_queue_info).collect();
+async fn list_queues_handler(req: HttpRequest, broker: web::Data<Broker>) -> HttpResponse {
+  let queue_map = broker.queues();
+  let queue_info: Vec<QueueInfo> = queue_map
+    .iter()
+    .filter(|(name, _)| can_see_queue(&req, name))
+    .map(|(_, queue)| format_queue_info(queue))
+    .collect();
 
   HttpResponse::Ok()
     .content_type("application/json")
@@ -104,34 +222,29 @@ async fn list_queues_handler() -> HttpResponse {
     ))
 }
 
-#[allow(unused_doc_comments)]
-/**
- * @api {get} /queue/:queue_name Get queue info
- * @apiName GetQueue
- * @apiGroup Queue
- *
- * @apiSuccess {String} result:queue:name Queue name
- * @apiSuccess {Number} result:queue:created_at Queue creation unix timestamp
- * @apiSuccess {Number} result:queue:size Queue size (length)
- * @apiSuccess {Number} result:queue:num_deduplicating Amount of tracked deduplication IDs
- * @apiSuccess {Number} result:queue:num_unacknowledged Amount of unacknowledged messages
- * @apiSuccess {Number} result:queue:num_acknowledged Amount of acknowledged (done) messages
- * @apiSuccess {Number} result:queue:num_deduplicated Amount of deduplicated items
- * @apiSuccess {Number} result:queue:deduplication_time Time for deduplication ID to expire
- * @apiSuccess {Number} result:queue:requeue_time Time for an unacknowledged message to get added back into the queue
- * @apiSuccess {Number} result:queue:max_length Queue max length
- * @apiSuccess {String} result:queue:persistent Whether the queue is saved on disk
- * @apiSuccess {Number} result:queue:dead_letter:name Dead letter queue target
- * @apiSuccess {Number} result:queue:dead_letter:threshold Dead letter queue threshold
- * @apiSuccess {Number} result:queue:memory_size Approximate memory usage of the queue
- *
- * @apiError 404 Queue not found
- */
+/// Get queue info
+#[utoipa::path(
+  get,
+  path = "/queue/{queue_name}",
+  tag = "Queue",
+  params(
+    ("queue_name" = String, Path, description = "Queue name"),
+  ),
+  responses(
+    (status = 200, description = "Queue info", body = Response<QueueResult>),
+    (status = 403, description = "API key is not allowed to access this queue", body = ErrorResponse),
+    (status = 404, description = "Queue not found", body = ErrorResponse),
+  ),
+)]
 #[get("/queue/{queue_name}")]
-async fn get_queue_handler(req: HttpRequest) -> HttpResponse {
-  if queue_exists(&req) {
+async fn get_queue_handler(req: HttpRequest, broker: web::Data<Broker>) -> HttpResponse {
+  if !can_see_queue(&req, req.match_info().query("queue_name")) {
+    return queue_forbidden();
+  }
+
+  if queue_exists(&broker, &req) {
     let queue_name: String = req.match_info().query("queue_name").parse().unwrap();
-    let queue_map = QUEUES.lock().unwrap();
+    let queue_map = broker.queues();
     let queue = queue_map.get(&queue_name).unwrap();
 
     HttpResponse::Ok()
@@ -148,47 +261,67 @@ async fn get_queue_handler(req: HttpRequest) -> HttpResponse {
   }
 }
 
-#[allow(unused_doc_comments)]
-/**
- * @api {put} /queue/:queue Create queue
- * @apiName CreateQueue
- * @apiGroup Queue
- *
- * @apiParam {String} query:requeue_time (Optional) Ack time in seconds
- * @apiParam {String} query:deduplication_time (Optional) Deduplication time in seconds
- * @apiParam {String} query:persistent (Optional) Set to "true" to make queue persistent
- * @apiParam {String} query:max_length (Optional) Queue max length
- * @apiParam {String} query:dead_letter_queue_name (Optional) Dead letter queue target
- * @apiParam {String} query:dead_letter_queue_threshold (Optional) Dead letter queue requeue threshold (default: 3)
- *
- * @apiError 400 Invalid time argument
- * @apiError 409 Queue already exists
- */
+/// Create queue
+///
+/// Unset options use the server's queue defaults
+#[utoipa::path(
+  put,
+  path = "/queue/{queue_name}",
+  tag = "Queue",
+  params(
+    ("queue_name" = String, Path, description = "Queue name"),
+    CreateQueueOptions,
+  ),
+  responses(
+    (status = 201, description = "Queue created", body = EmptyResponse),
+    (status = 400, description = "Invalid queue name or time argument", body = ErrorResponse),
+    (status = 403, description = "API key is not allowed to access this queue", body = ErrorResponse),
+    (status = 404, description = "Dead letter target not found", body = ErrorResponse),
+    (status = 409, description = "Queue already exists", body = ErrorResponse),
+  ),
+)]
 #[put("/queue/{queue_name}")]
-async fn create_queue_handler(req: HttpRequest) -> HttpResponse {
-  if queue_exists(&req) {
-    return HttpResponse::Conflict()
+async fn create_queue_handler(req: HttpRequest, broker: web::Data<Broker>) -> HttpResponse {
+  if let Err(res) = check_queue_access(&req, Operation::Admin) {
+    return res;
+  }
+
+  if queue_exists(&broker, &req) {
+    HttpResponse::Conflict()
       .content_type("application/json")
-      .body(format_error(409, String::from("Queue already exists")));
+      .body(format_error(409, String::from("Queue already exists")))
   } else {
     let queue_name: String = req.match_info().query("queue_name").parse().unwrap();
-    if queue_name.is_empty() || queue_name.len() > 64 {
+    if queue_name.is_empty() || queue_name.len() > config().limits.max_queue_name_length {
       return HttpResponse::BadRequest()
         .content_type("application/json")
         .body(format_error(400, String::from("Invalid queue name")));
     }
 
+    let defaults = config().queue_defaults.clone();
     let query_str = req.query_string();
     let qs = QString::from(query_str);
-    let requeue_time_str = qs.get("requeue_time").unwrap_or("300");
-    let deduplication_time_str = qs.get("deduplication_time").unwrap_or("300");
-    let max_length_str = qs.get("max_length").unwrap_or("0");
-
-    let requeue_time_result = requeue_time_str.parse::<u32>().ok();
-    let deduplication_time_result = deduplication_time_str.parse::<u32>().ok();
-    let max_length_result = max_length_str.parse::<u64>().ok();
 
-    let persistent = qs.get("persistent").unwrap_or("true") == "true";
+    let requeue_time_result = qs
+      .get("requeue_time")
+      .map_or(Some(defaults.requeue_time), |value| {
+        value
+          .parse::<f64>()
+          .ok()
+          .filter(|secs| is_valid_requeue_time(*secs))
+      });
+    let deduplication_time_result = qs
+      .get("deduplication_time")
+      .map_or(Some(defaults.deduplication_time), |value| {
+        value.parse::<u32>().ok()
+      });
+    let max_length_result = qs
+      .get("max_length")
+      .map_or(Some(defaults.max_length), |value| value.parse::<u64>().ok());
+
+    let persistent = qs
+      .get("persistent")
+      .map_or(defaults.persistent, |value| value == "true");
 
     if requeue_time_result.is_none() || deduplication_time_result.is_none() {
       return HttpResponse::BadRequest()
@@ -196,49 +329,38 @@ async fn create_queue_handler(req: HttpRequest) -> HttpResponse {
         .body(format_error(400, String::from("Invalid time argument")));
     }
 
-    let mut queue_map = QUEUES.lock().unwrap();
-
     let dead_letter_queue_name = qs.get("dead_letter_queue_name");
 
-    if dead_letter_queue_name.is_some() {
-      // Search for dead letter queue
-      // If not found, error with 404 // TODO: or 400?
-      let dead_letter_queue = queue_map.get(&String::from(dead_letter_queue_name.unwrap()));
-      if dead_letter_queue.is_none() {
-        return HttpResponse::NotFound()
-          .content_type("application/json")
-          .body(format_error(
-            404,
-            String::from("Dead letter target not found"),
-          ));
+    if let Some(dead_letter_queue_name) = dead_letter_queue_name {
+      // Dead lettered messages are enqueued into the target
+      if !can_access_queue(&req, dead_letter_queue_name, Operation::Produce) {
+        return queue_forbidden();
       }
     }
 
     let dead_letter_queue_threshold = qs
       .get("dead_letter_queue_threshold")
-      .unwrap_or("3")
-      .parse::<u16>()
-      .ok();
-
-    let dead_letter_queue = if dead_letter_queue_name.is_some() {
-      Some(QueueDeadLetterSettings {
-        name: String::from(dead_letter_queue_name.unwrap()),
-        threshold: dead_letter_queue_threshold.unwrap(),
-      })
-    } else {
-      None
-    };
-
-    let mut queue = Queue::new(
-      queue_name.clone(),
-      requeue_time_result.unwrap(),
-      deduplication_time_result.unwrap(),
+      .map_or(Some(defaults.dead_letter_threshold), |value| {
+        value.parse::<u16>().ok()
+      });
+
+    let dead_letter_queue = dead_letter_queue_name.map(|name| QueueDeadLetterSettings {
+      name: String::from(name),
+      threshold: dead_letter_queue_threshold.unwrap(),
+    });
+
+    let settings = QueueSettings {
+      requeue_time: requeue_time_result.unwrap(),
+      deduplication_time: deduplication_time_result.unwrap(),
       persistent,
-      max_length_result.unwrap(),
+      max_length: max_length_result.unwrap(),
       dead_letter_queue,
-    );
-    queue.start_compact_interval(get_compaction_interval());
-    queue_map.insert(queue_name.clone(), queue);
+    };
+    // Fails if the dead letter target does not exist (404)
+    // TODO: or 400?
+    if let Err(error) = broker.create_queue(&queue_name, settings) {
+      return broker_error_response(error);
+    }
 
     HttpResponse::Created()
       .content_type("application/json")
@@ -250,19 +372,8 @@ async fn create_queue_handler(req: HttpRequest) -> HttpResponse {
   }
 }
 
-#[derive(Serialize, Deserialize)]
-struct NewItem {
-  item: Value,
-  deduplication_id: Option<String>,
-}
-
-#[derive(Serialize, Deserialize)]
-struct EnqueueBody {
-  messages: Vec<NewItem>,
-}
-
 // TODO: unnecessary
-fn are_all_objects(vec: &Vec<NewItem>) -> bool {
+fn are_all_objects(vec: &[NewMessage]) -> bool {
   let mut all_objects = true;
 
   for item in vec.iter() {
@@ -274,69 +385,53 @@ fn are_all_objects(vec: &Vec<NewItem>) -> bool {
   all_objects
 }
 
-#[allow(unused_doc_comments)]
-/**
- * @api {post} /queue/:queue/enqueue Enqueue message(s)
- * @apiName EnqueueMessages
- * @apiGroup Queue
- *
- * @apiParam {Array} body:messages List of messages: { item: Object, deduplication: Nullable<String> }
- *
- * @apiSuccess {Number} result:num_enqueued Amount of enqueued messages
- * @apiSuccess {Number} result:num_deduplicated Amount of deduplicated messages
- * @apiSuccess {Array} result:items Created messages
- *
- * @apiError 400 Bad Request
- * @apiError 404 Queue not found
- */
+/// Enqueue messages
+///
+/// Messages with a deduplication ID that is still being tracked are dropped
+#[utoipa::path(
+  post,
+  path = "/queue/{queue_name}/enqueue",
+  tag = "Message",
+  params(
+    ("queue_name" = String, Path, description = "Queue name"),
+  ),
+  request_body = EnqueueBody,
+  responses(
+    (status = 202, description = "Messages enqueued", body = Response<EnqueueResult>),
+    (status = 400, description = "Invalid queue name, items have to be JSON objects & batches are limited to limits.max_enqueue_batch messages", body = ErrorResponse),
+    (status = 403, description = "API key is not allowed to access this queue, or the queue is full", body = ErrorResponse),
+    (status = 404, description = "Queue not found", body = ErrorResponse),
+  ),
+)]
 #[post("/queue/{queue_name}/enqueue")]
-async fn enqueue_handler(info: web::Json<EnqueueBody>, req: HttpRequest) -> HttpResponse {
+async fn enqueue_handler(
+  info: web::Json<EnqueueBody>,
+  req: HttpRequest,
+  broker: web::Data<Broker>,
+) -> HttpResponse {
+  if let Err(res) = check_queue_access(&req, Operation::Produce) {
+    return res;
+  }
+
   let body = &info;
 
-  if body.messages.len() < 256 && are_all_objects(&body.messages) {
+  let limits = config().limits.clone();
+  if body.messages.len() <= limits.max_enqueue_batch && are_all_objects(&body.messages) {
     let queue_name: String = req.match_info().query("queue_name").parse().unwrap();
-    if queue_name.is_empty() || queue_name.len() > 64 {
+    if queue_name.is_empty() || queue_name.len() > limits.max_queue_name_length {
       return HttpResponse::BadRequest()
         .content_type("application/json")
         .body(format_error(400, String::from("Invalid queue name")));
     }
 
-    if !queue_exists(&req) {
-      return HttpResponse::NotFound()
-        .content_type("application/json")
-        .body(format_error(404, String::from("Queue not found")));
-    }
-
-    let mut queue_map = QUEUES.lock().unwrap();
-    let queue = queue_map.get_mut(&queue_name).unwrap();
-
-    if !queue.can_fit_messages(body.messages.len() as u64) {
-      return HttpResponse::Forbidden()
-        .content_type("application/json")
-        .body(format_error(403, String::from("Queue is full")));
-    }
-
-    let mut enqueued_items: Vec<Message> = Vec::new();
-    let mut num_deduplicated = 0;
-
-    for item in body.messages.iter() {
-      let dup_item = item.clone();
-      let dedup_id = dup_item.deduplication_id.clone();
-      let dedup_id_as_string = if dedup_id.is_some() {
-        Some(String::from(dedup_id.clone().unwrap()))
-      } else {
-        None
-      };
-      let msg = queue.try_enqueue(dup_item.item.clone(), dedup_id_as_string);
-      if msg.is_some() {
-        enqueued_items.push(msg.unwrap());
-      } else {
-        num_deduplicated += 1;
-      }
-    }
-    let unwrapped_vec: Vec<Value> = enqueued_items.into_iter().map(unwrap_message).collect();
+    let result = match broker.enqueue(&queue_name, info.into_inner().messages) {
+      Ok(result) => result,
+      Err(error) => return broker_error_response(error),
+    };
+    let num_deduplicated = result.num_deduplicated;
+    let unwrapped_vec: Vec<ApiMessage> = result.enqueued.into_iter().map(unwrap_message).collect();
 
-    return HttpResponse::Accepted()
+    HttpResponse::Accepted()
       .content_type("application/json")
       .body(format_success(
         202,
@@ -346,111 +441,177 @@ async fn enqueue_handler(info: web::Json<EnqueueBody>, req: HttpRequest) -> Http
           "num_deduplicated": num_deduplicated,
           "items": unwrapped_vec
         }),
-      ));
+      ))
   } else {
-    return HttpResponse::BadRequest()
+    HttpResponse::BadRequest()
     .content_type("application/json")
-    .body(format_error(400, String::from("body.items is required to be of type Array<{ item: String, deduplication_id: String? }> with at most 255 items")));
+    .body(format_error(400, format!("body.items is required to be of type Array<{{ item: String, deduplication_id: String? }}> with at most {} items", limits.max_enqueue_batch)))
   }
 }
 
+/// Acknowledge message
+///
+/// Removes a dequeued message for good, before it is requeued
+#[utoipa::path(
+  post,
+  path = "/queue/{queue_name}/{message}/ack",
+  tag = "Message",
+  params(
+    ("queue_name" = String, Path, description = "Queue name"),
+    ("message" = String, Path, description = "Message ID"),
+  ),
+  responses(
+    (status = 200, description = "Message acknowledged", body = EmptyResponse),
+    (status = 403, description = "API key is not allowed to access this queue", body = ErrorResponse),
+    (status = 404, description = "Queue not found, or the message is not waiting to be acknowledged", body = ErrorResponse),
+  ),
+)]
 #[post("/queue/{queue_name}/{message}/ack")]
-async fn ack_handler(req: HttpRequest) -> HttpResponse {
-  if queue_exists(&req) {
-    let mut queue_map = QUEUES.lock().unwrap();
+async fn ack_handler(req: HttpRequest, broker: web::Data<Broker>) -> HttpResponse {
+  if let Err(res) = check_queue_access(&req, Operation::Consume) {
+    return res;
+  }
 
-    let queue_name: String = req.match_info().query("queue_name").parse().unwrap();
-    let message_id: String = req.match_info().query("message").parse().unwrap();
+  let queue_name = req.match_info().query("queue_name");
+  let message_id = req.match_info().query("message");
 
-    let queue = queue_map.get_mut(&queue_name).unwrap();
-    let ack_result = queue.ack(message_id);
+  match broker.ack(queue_name, message_id) {
+    Ok(()) => HttpResponse::Ok()
+      .content_type("application/json")
+      .body(format_success(
+        200,
+        String::from("Message reception acknowledged"),
+        json!(null),
+      )),
+    Err(error) => broker_error_response(error),
+  }
+}
 
-    if ack_result {
-      HttpResponse::Ok()
-        .content_type("application/json")
-        .body(format_success(
-          200,
-          String::from("Message reception acknowledged"),
-          json!(null),
-        ))
-    } else {
-      HttpResponse::NotFound()
-        .content_type("application/json")
-        .body(format_error(404, String::from("Message not found")))
-    }
-  } else {
-    HttpResponse::NotFound()
+/// Extend message lease
+///
+/// Postpones requeuing an unacknowledged message, so long running consumers keep it
+#[utoipa::path(
+  post,
+  path = "/queue/{queue_name}/{message}/extend",
+  tag = "Message",
+  params(
+    ("queue_name" = String, Path, description = "Queue name"),
+    ("message" = String, Path, description = "Message ID"),
+    ("seconds" = Option<f64>, Query, description = "Time from now until the message is requeued, defaults to the queue's requeue time"),
+  ),
+  responses(
+    (status = 200, description = "Lease extended", body = Response<ExtendResult>),
+    (status = 400, description = "Invalid time argument", body = ErrorResponse),
+    (status = 403, description = "API key is not allowed to access this queue", body = ErrorResponse),
+    (status = 404, description = "Queue not found, or the message is not waiting to be acknowledged", body = ErrorResponse),
+  ),
+)]
+#[post("/queue/{queue_name}/{message}/extend")]
+async fn extend_handler(req: HttpRequest, broker: web::Data<Broker>) -> HttpResponse {
+  if let Err(res) = check_queue_access(&req, Operation::Consume) {
+    return res;
+  }
+
+  let queue_name = req.match_info().query("queue_name");
+  let message_id = req.match_info().query("message");
+
+  let qs = QString::from(req.query_string());
+  let lifetime = match qs.get("seconds") {
+    None => None,
+    Some(value) => match value
+      .parse::<f64>()
+      .ok()
+      .filter(|secs| *secs > 0.0 && is_valid_requeue_time(*secs))
+    {
+      Some(secs) => Some(Duration::from_secs_f64(secs)),
+      None => {
+        return HttpResponse::BadRequest()
+          .content_type("application/json")
+          .body(format_error(400, String::from("Invalid time argument")));
+      }
+    },
+  };
+
+  match broker.extend_lease(queue_name, message_id, lifetime) {
+    Ok(requeue_at) => HttpResponse::Ok()
       .content_type("application/json")
-      .body(format_error(404, String::from("Queue not found")))
+      .body(format_success(
+        200,
+        String::from("Message lease extended"),
+        json!({ "requeue_at": requeue_at }),
+      )),
+    Err(error) => broker_error_response(error),
   }
 }
 
-#[allow(unused_doc_comments)]
-/**
- * @api {get} /queue/:queue/peek Peek queue head
- * @apiName PeekQueue
- * @apiGroup Queue
- *
- * @apiSuccess {Array} result:item Queue head
- *
- * @apiError 404 Queue not found
- */
+/// Peek queue head
+#[utoipa::path(
+  get,
+  path = "/queue/{queue_name}/peek",
+  tag = "Message",
+  params(
+    ("queue_name" = String, Path, description = "Queue name"),
+  ),
+  responses(
+    (status = 200, description = "Queue head, null if the queue is empty", body = Response<PeekResult>),
+    (status = 403, description = "API key is not allowed to access this queue", body = ErrorResponse),
+    (status = 404, description = "Queue not found", body = ErrorResponse),
+  ),
+)]
 #[get("/queue/{queue_name}/peek")]
-async fn peek_handler(req: HttpRequest) -> HttpResponse {
-  if queue_exists(&req) {
-    let mut queue_map = QUEUES.lock().unwrap();
-    let queue_name: String = req.match_info().query("queue_name").parse().unwrap();
-    let queue = queue_map.get_mut(&queue_name).unwrap();
-
-    let message = queue.peek();
+async fn peek_handler(req: HttpRequest, broker: web::Data<Broker>) -> HttpResponse {
+  if let Err(res) = check_queue_access(&req, Operation::Consume) {
+    return res;
+  }
 
-    if message.is_some() {
-      return HttpResponse::Ok()
-        .content_type("application/json")
-        .body(format_success(
-          200,
-          String::from("Message retrieved successfully"),
-          json!({
-            "item": unwrap_message(message.unwrap())
-          }),
-        ));
-    } else {
-      return HttpResponse::Ok()
-        .content_type("application/json")
-        .body(format_success(
-          200,
-          String::from("Queue is empty"),
-          json!({ "item": null }),
-        ));
-    }
-  } else {
-    HttpResponse::NotFound()
+  match broker.peek(req.match_info().query("queue_name")) {
+    Ok(Some(message)) => HttpResponse::Ok()
       .content_type("application/json")
-      .body(format_error(404, String::from("Queue not found")))
+      .body(format_success(
+        200,
+        String::from("Message retrieved successfully"),
+        json!({
+          "item": unwrap_message(message)
+        }),
+      )),
+    Ok(None) => HttpResponse::Ok()
+      .content_type("application/json")
+      .body(format_success(
+        200,
+        String::from("Queue is empty"),
+        json!({ "item": null }),
+      )),
+    Err(error) => broker_error_response(error),
   }
 }
 
-#[allow(unused_doc_comments)]
-/**
- * @api {post} /queue/:queue/dequeue Dequeue message(s)
- * @apiName DequeueMessages
- * @apiGroup Queue
- *
- * @apiParam {String} query:ack (Optional) Set to "true" to automatically acknowledge message(s)
- * @apiParam {String} query:amount (Optional) Amount of items to dequeue and return
- *
- * @apiSuccess {Array} result:items Dequeued messages
- * @apiSuccess {Array} result:num_items Amount of dequeued messages
- *
- * @apiError 400 Invalid amount parameter
- * @apiError 404 Queue not found
- */
+/// Dequeue messages
+///
+/// Unless acknowledged, dequeued messages are requeued after the queue's requeue time
+#[utoipa::path(
+  post,
+  path = "/queue/{queue_name}/dequeue",
+  tag = "Message",
+  params(
+    ("queue_name" = String, Path, description = "Queue name"),
+    ("ack" = Option<bool>, Query, description = "Acknowledge the messages right away"),
+    ("amount" = Option<u8>, Query, description = "Maximum amount of messages to dequeue (default: 1)"),
+  ),
+  responses(
+    (status = 200, description = "Dequeued messages", body = Response<DequeueResult>),
+    (status = 400, description = "Invalid amount parameter", body = ErrorResponse),
+    (status = 403, description = "API key is not allowed to access this queue", body = ErrorResponse),
+    (status = 404, description = "Queue not found", body = ErrorResponse),
+  ),
+)]
 #[post("/queue/{queue_name}/dequeue")]
-async fn dequeue_handler(req: HttpRequest) -> HttpResponse {
-  if queue_exists(&req) {
-    let mut queue_map = QUEUES.lock().unwrap();
-    let queue_name: String = req.match_info().query("queue_name").parse().unwrap();
-    let queue = queue_map.get_mut(&queue_name).unwrap();
+async fn dequeue_handler(req: HttpRequest, broker: web::Data<Broker>) -> HttpResponse {
+  if let Err(res) = check_queue_access(&req, Operation::Consume) {
+    return res;
+  }
+
+  if queue_exists(&broker, &req) {
+    let queue_name = req.match_info().query("queue_name");
 
     let query_str = req.query_string();
     let qs = QString::from(query_str);
@@ -463,20 +624,12 @@ async fn dequeue_handler(req: HttpRequest) -> HttpResponse {
         .content_type("application/json")
         .body(format_error(400, String::from("Invalid amount parameter")))
     } else {
-      let mut dequeued_items: Vec<Message> = Vec::new();
-      let mut i = 0;
-
-      while i < max {
-        let message = queue.dequeue(auto_ack == "true");
-        if message.is_some() {
-          dequeued_items.push(message.unwrap());
-          i += 1;
-        } else {
-          break;
-        }
-      }
+      let dequeued_items = match broker.dequeue(queue_name, max.into(), auto_ack == "true") {
+        Ok(messages) => messages,
+        Err(error) => return broker_error_response(error),
+      };
 
-      let unwrapped_vec: Vec<Value> = dequeued_items.into_iter().map(unwrap_message).collect();
+      let unwrapped_vec: Vec<ApiMessage> = dequeued_items.into_iter().map(unwrap_message).collect();
 
       HttpResponse::Ok()
         .content_type("application/json")
@@ -496,27 +649,217 @@ async fn dequeue_handler(req: HttpRequest) -> HttpResponse {
   }
 }
 
-#[derive(Serialize, Deserialize)]
-struct QueuePatchBody {
-  requeue_time: Option<u32>,
-  deduplication_time: Option<u32>,
-  max_length: Option<u64>,
+/// Export messages
+///
+/// Streams all queued messages as JSON Lines, one message per line, in queue order
+#[utoipa::path(
+  get,
+  path = "/queue/{queue_name}/export",
+  tag = "Queue",
+  params(
+    ("queue_name" = String, Path, description = "Queue name"),
+    ("include_in_flight" = Option<bool>, Query, description = "Also export unacknowledged messages (exported first)"),
+    ("queued_since" = Option<u64>, Query, description = "Only export messages queued at or after this Unix timestamp (in milliseconds)"),
+  ),
+  responses(
+    (status = 200, description = "One message per line", body = ApiMessage, content_type = "application/x-ndjson"),
+    (status = 400, description = "Invalid time argument", body = ErrorResponse),
+    (status = 403, description = "API key is not allowed to access this queue", body = ErrorResponse),
+    (status = 404, description = "Queue not found", body = ErrorResponse),
+  ),
+)]
+#[get("/queue/{queue_name}/export")]
+async fn export_handler(req: HttpRequest, broker: web::Data<Broker>) -> HttpResponse {
+  if let Err(res) = check_queue_access(&req, Operation::Admin) {
+    return res;
+  }
+
+  if !queue_exists(&broker, &req) {
+    return HttpResponse::NotFound()
+      .content_type("application/json")
+      .body(format_error(404, String::from("Queue not found")));
+  }
+
+  let qs = QString::from(req.query_string());
+  let include_in_flight = qs.get("include_in_flight").unwrap_or("false") == "true";
+  let queued_since = match qs.get("queued_since").map(str::parse::<u64>) {
+    None => 0,
+    Some(Ok(queued_since)) => queued_since,
+    Some(Err(_)) => {
+      return HttpResponse::BadRequest()
+        .content_type("application/json")
+        .body(format_error(400, String::from("Invalid time argument")))
+    }
+  };
+
+  // Only copy the messages while the broker is locked, they are serialized while streaming
+  let messages: Vec<Message> = {
+    let queue_map = broker.queues();
+    let queue_name: String = req.match_info().query("queue_name").parse().unwrap();
+    let queue = queue_map.get(&queue_name).unwrap();
+    let in_flight = if include_in_flight {
+      queue.unacked_items()
+    } else {
+      Vec::new()
+    };
+    in_flight
+      .into_iter()
+      .chain(queue.items().iter())
+      .filter(|msg| msg.queued_at() >= queued_since)
+      .cloned()
+      .collect()
+  };
+  let lines = messages.into_iter().map(|msg| {
+    let line = serde_json::to_string(&unwrap_message(msg)).unwrap();
+    Ok::<_, actix_web::Error>(Bytes::from(format!("{}\n", line)))
+  });
+
+  HttpResponse::Ok()
+    .content_type("application/x-ndjson")
+    .streaming(stream::iter(lines))
+}
+
+/// Import messages
+///
+/// Appends messages in the format of the export route, keeping their IDs, timestamps, states & requeue counts.
+/// Messages whose ID is already in the queue are skipped
+#[utoipa::path(
+  post,
+  path = "/queue/{queue_name}/import",
+  tag = "Queue",
+  params(
+    ("queue_name" = String, Path, description = "Queue name"),
+  ),
+  request_body(content = ApiMessage, content_type = "application/x-ndjson", description = "One message per line"),
+  responses(
+    (status = 202, description = "Messages imported", body = Response<ImportResult>),
+    (status = 400, description = "Invalid message", body = ErrorResponse),
+    (status = 403, description = "API key is not allowed to access this queue, or the queue is full", body = ErrorResponse),
+    (status = 404, description = "Queue not found", body = ErrorResponse),
+    (status = 413, description = "Body is larger than the configured limit", body = ErrorResponse),
+  ),
+)]
+#[post("/queue/{queue_name}/import")]
+async fn import_handler(
+  mut body: web::Payload,
+  req: HttpRequest,
+  broker: web::Data<Broker>,
+) -> HttpResponse {
+  if let Err(res) = check_queue_access(&req, Operation::Admin) {
+    return res;
+  }
+
+  if !queue_exists(&broker, &req) {
+    return HttpResponse::NotFound()
+      .content_type("application/json")
+      .body(format_error(404, String::from("Queue not found")));
+  }
+
+  let max_body_size = config().limits.max_body_size;
+  let mut bytes = BytesMut::new();
+  while let Some(chunk) = body.next().await {
+    match chunk {
+      Ok(chunk) => {
+        if bytes.len() + chunk.len() > max_body_size {
+          return HttpResponse::PayloadTooLarge()
+            .content_type("application/json")
+            .body(format_error(
+              413,
+              format!("Body is larger than {} bytes", max_body_size),
+            ));
+        }
+        bytes.extend_from_slice(&chunk)
+      }
+      Err(_) => {
+        return HttpResponse::BadRequest()
+          .content_type("application/json")
+          .body(format_error(400, String::from("Failed to read body")))
+      }
+    }
+  }
+
+  let text = match std::str::from_utf8(&bytes) {
+    Ok(text) => text,
+    Err(_) => {
+      return HttpResponse::BadRequest()
+        .content_type("application/json")
+        .body(format_error(400, String::from("Body is not valid UTF-8")))
+    }
+  };
+
+  let mut messages: Vec<Message> = Vec::new();
+  for (index, line) in text.lines().enumerate() {
+    if line.trim().is_empty() {
+      continue;
+    }
+    let message = serde_json::from_str(line).ok().and_then(wrap_message);
+    if let Some(message) = message {
+      messages.push(message);
+    } else {
+      return HttpResponse::BadRequest()
+        .content_type("application/json")
+        .body(format_error(
+          400,
+          format!("Invalid message in line {}", index + 1),
+        ));
+    }
+  }
+
+  let mut queue_map = broker.queues();
+  let queue_name: String = req.match_info().query("queue_name").parse().unwrap();
+  let queue = queue_map.get_mut(&queue_name).unwrap();
+
+  // Messages that are already in the queue don't take up space
+  let num_messages = messages.len();
+  let messages = queue.filter_new_messages(messages);
+  if !queue.can_fit_messages(messages.len() as u64) {
+    return HttpResponse::Forbidden()
+      .content_type("application/json")
+      .body(format_error(403, String::from("Queue is full")));
+  }
+
+  let num_imported = queue.import_messages(messages);
+
+  HttpResponse::Accepted()
+    .content_type("application/json")
+    .body(format_success(
+      202,
+      String::from("Request processed successfully"),
+      json!({
+        "num_imported": num_imported,
+        "num_skipped": num_messages - num_imported,
+      }),
+    ))
 }
 
-#[allow(unused_doc_comments)]
-/**
- * @api {patch} /queue/:queue Edit queue
- * @apiName EditQueue
- * @apiGroup Queue
- *
- * @apiParam {String} body:requeue_time (Optional) Ack time in seconds
- * @apiParam {String} body:deduplication_time (Optional) Deduplication time in seconds
- *
- * @apiError 400 Invalid input
- * @apiError 404 Queue not found
- */
+/// Edit queue
+///
+/// Unset fields are kept
+#[utoipa::path(
+  patch,
+  path = "/queue/{queue_name}",
+  tag = "Queue",
+  params(
+    ("queue_name" = String, Path, description = "Queue name"),
+  ),
+  request_body = QueuePatchBody,
+  responses(
+    (status = 200, description = "Queue edited", body = EmptyResponse),
+    (status = 400, description = "Invalid queue name or time argument", body = ErrorResponse),
+    (status = 403, description = "API key is not allowed to access this queue", body = ErrorResponse),
+    (status = 404, description = "Queue not found", body = ErrorResponse),
+  ),
+)]
 #[patch("/queue/{queue_name}")]
-async fn edit_queue_handler(info: web::Json<QueuePatchBody>, req: HttpRequest) -> HttpResponse {
+async fn edit_queue_handler(
+  info: web::Json<QueuePatchBody>,
+  req: HttpRequest,
+  broker: web::Data<Broker>,
+) -> HttpResponse {
+  if let Err(res) = check_queue_access(&req, Operation::Admin) {
+    return res;
+  }
+
   let body = info;
 
   let queue_name: String = req.match_info().query("queue_name").parse().unwrap();
@@ -527,25 +870,37 @@ async fn edit_queue_handler(info: web::Json<QueuePatchBody>, req: HttpRequest) -
       .body(format_error(400, String::from("Invalid queue name")));
   }
 
-  if !queue_exists(&req) {
+  if body
+    .requeue_time
+    .is_some_and(|secs| !is_valid_requeue_time(secs))
+  {
+    return HttpResponse::BadRequest()
+      .content_type("application/json")
+      .body(format_error(400, String::from("Invalid time argument")));
+  }
+
+  if !queue_exists(&broker, &req) {
     return HttpResponse::NotFound()
       .content_type("application/json")
       .body(format_error(404, String::from("Queue not found")));
   }
 
-  let mut queue_map = QUEUES.lock().unwrap();
+  let mut queue_map = broker.queues();
   let queue = queue_map.get_mut(&queue_name).unwrap();
 
-  if body.deduplication_time.is_some() {
-    let value = body.deduplication_time.unwrap();
-    eprintln!("{}: Setting deduplication_time to {}", queue_name, value);
+  if let Some(value) = body.deduplication_time {
+    info!("{}: Setting deduplication_time to {}", queue_name, value);
     queue.set_deduplication_time(value);
   }
 
-  if body.requeue_time.is_some() {
-    let value = body.requeue_time.unwrap();
-    eprintln!("{}: Setting requeue_time to {}", queue_name, value);
-    queue.set_requeue_time(body.requeue_time.unwrap());
+  if let Some(value) = body.requeue_time {
+    info!("{}: Setting requeue_time to {}", queue_name, value);
+    queue.set_requeue_time(value);
+  }
+
+  if let Some(value) = body.max_length {
+    info!("{}: Setting max_length to {}", queue_name, value);
+    queue.set_max_length(value);
   }
 
   if queue.is_persistent() {
@@ -561,22 +916,34 @@ async fn edit_queue_handler(info: web::Json<QueuePatchBody>, req: HttpRequest) -
     ))
 }
 
-#[allow(unused_doc_comments)]
-/**
- * @api {post} /queue/:queue/compact Compact queue file
- * @apiName CompactQueue
- * @apiGroup Queue
- *
- * @apiError 404 Queue not found
- */
+/// Compact queue file
+///
+/// Rewrites the item file of a persistent queue without the removed messages
+#[utoipa::path(
+  post,
+  path = "/queue/{queue_name}/compact",
+  tag = "Queue",
+  params(
+    ("queue_name" = String, Path, description = "Queue name"),
+  ),
+  responses(
+    (status = 200, description = "Queue compacted", body = EmptyResponse),
+    (status = 403, description = "API key is not allowed to access this queue, or the queue is not persistent", body = ErrorResponse),
+    (status = 404, description = "Queue not found", body = ErrorResponse),
+  ),
+)]
 #[post("/queue/{queue_name}/compact")]
-async fn compact_handler(req: HttpRequest) -> HttpResponse {
-  if !queue_exists(&req) {
-    return HttpResponse::NotFound()
+async fn compact_handler(req: HttpRequest, broker: web::Data<Broker>) -> HttpResponse {
+  if let Err(res) = check_queue_access(&req, Operation::Admin) {
+    return res;
+  }
+
+  if !queue_exists(&broker, &req) {
+    HttpResponse::NotFound()
       .content_type("application/json")
-      .body(format_error(404, String::from("Queue not found")));
+      .body(format_error(404, String::from("Queue not found")))
   } else {
-    let mut queue_map = QUEUES.lock().unwrap();
+    let mut queue_map = broker.queues();
 
     let queue_name: String = req.match_info().query("queue_name").parse().unwrap();
     let queue = queue_map.get_mut(&queue_name).unwrap();
@@ -603,111 +970,289 @@ async fn compact_handler(req: HttpRequest) -> HttpResponse {
 }
 
 // TODO: should probably be POST
-#[allow(unused_doc_comments)]
-/**
- * @api {delete} /queue/:queue/purge Purge queue
- * @apiName PurgeQueue
- * @apiGroup Queue
- *
- * @apiError 404 Queue not found
- */
+/// Purge queue
+///
+/// Removes all messages of the queue
+#[utoipa::path(
+  delete,
+  path = "/queue/{queue_name}/purge",
+  tag = "Queue",
+  params(
+    ("queue_name" = String, Path, description = "Queue name"),
+  ),
+  responses(
+    (status = 200, description = "Queue purged", body = EmptyResponse),
+    (status = 403, description = "API key is not allowed to access this queue", body = ErrorResponse),
+    (status = 404, description = "Queue not found", body = ErrorResponse),
+  ),
+)]
 #[delete("/queue/{queue_name}/purge")]
-async fn purge_handler(req: HttpRequest) -> HttpResponse {
-  if !queue_exists(&req) {
-    return HttpResponse::NotFound()
-      .content_type("application/json")
-      .body(format_error(404, String::from("Queue not found")));
-  } else {
-    let mut queue_map = QUEUES.lock().unwrap();
-
-    let queue_name: String = req.match_info().query("queue_name").parse().unwrap();
-    let queue = queue_map.get_mut(&queue_name).unwrap();
-    queue.purge(false);
+async fn purge_handler(req: HttpRequest, broker: web::Data<Broker>) -> HttpResponse {
+  if let Err(res) = check_queue_access(&req, Operation::Admin) {
+    return res;
+  }
 
-    HttpResponse::Ok()
+  match broker.purge_queue(req.match_info().query("queue_name")) {
+    Ok(()) => HttpResponse::Ok()
       .content_type("application/json")
       .body(format_success(
         200,
         String::from("Queue purged successfully"),
         json!(null),
-      ))
+      )),
+    Err(error) => broker_error_response(error),
   }
 }
 
-fn is_dead_letter_queue(queues: Vec<&Queue>, queue_name: &String) -> bool {
-  queues.iter().any(|x| {
-    let meta = x.get_meta();
-    if meta.dead_letter_queue.is_some() {
-      if meta.dead_letter_queue.unwrap().name == *queue_name {
-        return true;
-      }
-    }
-    false
-  })
-}
-
-#[allow(unused_doc_comments)]
-/**
- * @api {delete} /queue/:queue Delete queue
- * @apiName DeleteQueue
- * @apiGroup Queue
- *
- * @apiError 404 Queue not found
- */
+/// Delete queue
+#[utoipa::path(
+  delete,
+  path = "/queue/{queue_name}",
+  tag = "Queue",
+  params(
+    ("queue_name" = String, Path, description = "Queue name"),
+  ),
+  responses(
+    (status = 200, description = "Queue deleted", body = EmptyResponse),
+    (status = 403, description = "API key is not allowed to access this queue, or the queue is the dead letter queue of another queue", body = ErrorResponse),
+    (status = 404, description = "Queue not found", body = ErrorResponse),
+  ),
+)]
 #[delete("/queue/{queue_name}")]
-async fn delete_handler(req: HttpRequest) -> HttpResponse {
-  if !queue_exists(&req) {
-    return HttpResponse::NotFound()
+async fn delete_handler(req: HttpRequest, broker: web::Data<Broker>) -> HttpResponse {
+  if let Err(res) = check_queue_access(&req, Operation::Admin) {
+    return res;
+  }
+
+  match broker.delete_queue(req.match_info().query("queue_name")) {
+    Ok(()) => HttpResponse::Ok()
       .content_type("application/json")
-      .body(format_error(404, String::from("Queue not found")));
-  } else {
-    let mut queue_map = QUEUES.lock().unwrap();
+      .body(format_success(
+        200,
+        String::from("Queue deleted successfully"),
+        json!(null),
+      )),
+    Err(error) => broker_error_response(error),
+  }
+}
 
-    let queue_name: String = req.match_info().query("queue_name").parse().unwrap();
+/// Liveness probe
+///
+/// Succeeds as long as the server process handles requests
+#[utoipa::path(
+  get,
+  path = "/health/live",
+  tag = "Server",
+  responses(
+    (status = 200, description = "Server is alive", body = EmptyResponse),
+  ),
+  security(()),
+)]
+#[get("/health/live")]
+async fn liveness_handler() -> HttpResponse {
+  HttpResponse::Ok()
+    .content_type("application/json")
+    .body(format_success(
+      200,
+      String::from("Server is alive"),
+      json!(null),
+    ))
+}
 
-    if is_dead_letter_queue(queue_map.values().collect(), &queue_name) {
-      return HttpResponse::Forbidden()
+/// Readiness probe
+///
+/// Fails while the stored queues are being loaded, during shutdown, if the data folder is not writable
+/// or if free disk space is below CORINTH_MIN_FREE_SPACE
+#[utoipa::path(
+  get,
+  path = "/health/ready",
+  tag = "Server",
+  responses(
+    (status = 200, description = "Server is ready", body = Response<ReadinessResult>),
+    (status = 503, description = "Server is not ready", body = ErrorResponse),
+  ),
+  security(()),
+)]
+#[get("/health/ready")]
+async fn readiness_handler() -> HttpResponse {
+  // The probe file & disk space checks block, so they run on the thread pool
+  let checks = match web::block(|| Ok::<_, ()>(check_readiness())).await {
+    Ok(checks) => checks,
+    Err(_) => {
+      return HttpResponse::ServiceUnavailable()
         .content_type("application/json")
         .body(format_error(
-          403,
-          String::from("Dead letter queue is in use"),
-        ));
+          503,
+          String::from("Server is not ready: readiness checks failed"),
+        ))
     }
+  };
+  let failed: Vec<String> = checks
+    .iter()
+    .filter(|check| !check.ok)
+    .map(|check| check.message.clone())
+    .collect();
+
+  if !failed.is_empty() {
+    return HttpResponse::ServiceUnavailable()
+      .content_type("application/json")
+      .body(format_error(
+        503,
+        format!("Server is not ready: {}", failed.join("; ")),
+      ));
+  }
 
-    let mut queue = queue_map.remove(&queue_name).unwrap();
-    queue.purge(true);
+  HttpResponse::Ok()
+    .content_type("application/json")
+    .body(format_success(
+      200,
+      String::from("Server is ready"),
+      json!({
+        "checks": checks,
+      }),
+    ))
+}
 
-    HttpResponse::Ok()
+#[cfg(debug_assertions)]
+#[derive(Deserialize)]
+pub struct AdvanceClockQuery {
+  ms: u64,
+}
+
+// Only available in debug builds, not part of the OpenAPI document
+// Moves the clock used for timestamps, ack & deduplication expiration and compaction forward by ms milliseconds,
+// then waits until all timers that became due have run
+#[cfg(debug_assertions)]
+#[post("/debug/clock/advance")]
+async fn advance_clock_handler(query: web::Query<AdvanceClockQuery>) -> HttpResponse {
+  let duration = std::time::Duration::from_millis(query.ms);
+  let now = web::block(move || Ok::<_, ()>(advance_clock(duration)))
+    .await
+    .unwrap_or_else(|_| timestamp());
+  info!("Advanced clock by {} ms", query.ms);
+
+  HttpResponse::Ok()
+    .content_type("application/json")
+    .body(format_success(
+      200,
+      String::from("Clock advanced successfully"),
+      json!({ "now": now }),
+    ))
+}
+
+// Registers all routes, used by the server & the tests
+pub fn configure_app(cfg: &mut web::ServiceConfig) {
+  cfg
+    .app_data(web::JsonConfig::default().limit(config().limits.max_body_size))
+    .service(index_handler)
+    .service(server_info_handler)
+    .service(favicon_handler)
+    .service(docs_handler)
+    .service(swagger_ui_handler)
+    .service(openapi_handler)
+    .service(create_queue_handler)
+    .service(list_queues_handler)
+    .service(get_queue_handler)
+    .service(enqueue_handler)
+    .service(ack_handler)
+    .service(extend_handler)
+    .service(peek_handler)
+    .service(export_handler)
+    .service(import_handler)
+    .service(dequeue_handler)
+    .service(edit_queue_handler)
+    .service(compact_handler)
+    .service(purge_handler)
+    .service(delete_handler)
+    .service(snapshot_handler)
+    .service(metrics_handler)
+    .service(liveness_handler)
+    .service(readiness_handler)
+    .service(close_handler);
+  debug_routes(cfg);
+}
+
+// Routes only registered in debug builds
+fn debug_routes(cfg: &mut web::ServiceConfig) {
+  #[cfg(debug_assertions)]
+  cfg.service(advance_clock_handler);
+  #[cfg(not(debug_assertions))]
+  let _ = cfg;
+}
+
+/// Prometheus metrics
+///
+/// Per-queue gauges & counters and request latency histograms (by route & status code)
+#[utoipa::path(
+  get,
+  path = "/metrics",
+  tag = "Server",
+  responses(
+    (status = 200, description = "Metrics in the Prometheus text format", body = String, content_type = "text/plain; version=0.0.4"),
+  ),
+)]
+#[get("/metrics")]
+async fn metrics_handler(broker: web::Data<Broker>) -> HttpResponse {
+  HttpResponse::Ok()
+    .content_type("text/plain; version=0.0.4")
+    .body(render_metrics(&broker))
+}
+
+/// Snapshot persistent queues
+///
+/// Returns a point-in-time tar archive of all persistent queues (metadata, items & in-flight messages).
+/// Restore it by starting the server with CORINTH_RESTORE_SNAPSHOT set to the archive path
+#[utoipa::path(
+  get,
+  path = "/snapshot",
+  tag = "Server",
+  responses(
+    (status = 200, description = "Tar archive", body = Vec<u8>, content_type = "application/x-tar"),
+    (status = 500, description = "Failed to create snapshot", body = ErrorResponse),
+  ),
+)]
+#[get("/snapshot")]
+async fn snapshot_handler(broker: web::Data<Broker>) -> HttpResponse {
+  let archive = web::block(move || Ok::<_, ()>(create_snapshot(&broker))).await;
+
+  match archive {
+    Ok(archive) => HttpResponse::Ok()
+      .content_type("application/x-tar")
+      .header(
+        "Content-Disposition",
+        format!(
+          "attachment; filename=\"corinth-snapshot-{}.tar\"",
+          timestamp()
+        ),
+      )
+      .body(archive),
+    Err(_) => HttpResponse::InternalServerError()
       .content_type("application/json")
-      .body(format_success(
-        200,
-        String::from("Queue deleted successfully"),
-        json!(null),
-      ))
+      .body(format_error(500, String::from("Failed to create snapshot"))),
   }
 }
 
-#[allow(unused_doc_comments)]
-/**
- * @api {post} /close Shuts down server
- * @apiName ShutdownServer
- * @apiGroup Server
- */
+/// Shut down server
+///
+/// Stops accepting new requests, lets running requests finish, then persists all queues and exits
+#[utoipa::path(
+  post,
+  path = "/close",
+  tag = "Server",
+  responses(
+    (status = 200, description = "Server is shutting down", body = EmptyResponse),
+  ),
+)]
 #[post("/close")]
 async fn close_handler() -> HttpResponse {
-  thread::spawn(move || {
-    eprintln!("Shutting down in 3 seconds...");
-    thread::sleep(Duration::from_secs(3));
-    eprintln!("Shutting down.");
-    std::process::exit(0);
-  });
+  begin_shutdown("Shutdown requested");
 
   // TODO: 202 Accepted?
   HttpResponse::Ok()
     .content_type("application/json")
     .body(format_success(
       200,
-      String::from("Server shuts down in 3 seconds"),
+      String::from("Server is shutting down"),
       json!(null),
     ))
 }
@@ -0,0 +1,87 @@
+use crate::env::{get_volatile_snapshot_mode, VolatileSnapshotMode};
+use crate::global_data::{get_start_time, queues_loaded};
+use actix_web::dev::Server;
+use actix_web::rt;
+use corinth::date::elapsed_secs;
+use corinth::Broker;
+use lazy_static::lazy_static;
+use log::{info, warn};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+lazy_static! {
+  static ref SERVER: Mutex<Option<Server>> = Mutex::new(None);
+}
+
+static SHUTTING_DOWN: AtomicBool = AtomicBool::new(false);
+
+pub fn register_server(server: Server) {
+  *SERVER.lock().unwrap() = Some(server);
+}
+
+pub fn is_shutting_down() -> bool {
+  SHUTTING_DOWN.load(Ordering::SeqCst)
+}
+
+// Stops accepting new work and lets running requests finish
+// The server gives up on requests after the drain timeout (CORINTH_SHUTDOWN_TIMEOUT)
+pub fn begin_shutdown(reason: &str) {
+  if SHUTTING_DOWN.swap(true, Ordering::SeqCst) {
+    return;
+  }
+  info!("{}, shutting down...", reason);
+
+  let server = SERVER.lock().unwrap().clone();
+  if let Some(server) = server {
+    rt::spawn(async move {
+      server.stop(true).await;
+    });
+  }
+}
+
+// Starts a graceful shutdown on SIGINT or SIGTERM
+#[cfg(unix)]
+pub async fn listen_for_signals() {
+  use actix_web::rt::signal::ctrl_c;
+  use actix_web::rt::signal::unix::{signal, SignalKind};
+  use futures::future::select;
+
+  let mut terminate = signal(SignalKind::terminate()).expect("Failed to listen for SIGTERM");
+  select(Box::pin(ctrl_c()), Box::pin(terminate.recv())).await;
+  begin_shutdown("Signal received");
+}
+
+#[cfg(not(unix))]
+pub async fn listen_for_signals() {
+  actix_web::rt::signal::ctrl_c().await.ok();
+  begin_shutdown("Signal received");
+}
+
+// Persists everything after the server has stopped
+pub fn finish_shutdown(broker: &Broker) {
+  // Stopped while loading: the broker only holds part of the queues, saving it
+  // would overwrite the stored metadata & volatile queues with incomplete data
+  if !queues_loaded() {
+    warn!("Shutdown before the queues were loaded, nothing is saved");
+    return;
+  }
+  broker.flush();
+  let mode = get_volatile_snapshot_mode();
+  if mode != VolatileSnapshotMode::Off {
+    broker.save_volatile_queues(mode == VolatileSnapshotMode::Contents);
+  }
+
+  let queue_map = broker.queues();
+  let num_persistent = queue_map.values().filter(|q| q.is_persistent()).count();
+  let num_queued: usize = queue_map.values().map(|q| q.size()).sum();
+  let num_unacked: usize = queue_map.values().map(|q| q.ack_size()).sum();
+
+  info!(
+    "Shutdown complete: {} queues ({} persistent), {} messages queued, {} messages unacknowledged, uptime {} secs",
+    queue_map.len(),
+    num_persistent,
+    num_queued,
+    num_unacked,
+    elapsed_secs(get_start_time())
+  );
+}
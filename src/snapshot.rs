@@ -0,0 +1,209 @@
+use corinth::date::timestamp;
+use corinth::{Broker, Message};
+use log::{debug, info, warn};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs::{canonicalize, create_dir_all, read_to_string, remove_dir_all, write, File};
+use std::io::Read;
+use std::path::{Component, Path};
+use std::time::UNIX_EPOCH;
+use tar::{Archive, Builder, Header};
+
+const META_FILE: &str = "meta.json";
+const ITEMS_FILE: &str = "items.jsonl";
+const INFLIGHT_FILE: &str = "inflight.jsonl";
+// Written to the data folder after a restore, identifies the restored archive
+const RESTORED_MARKER_FILE: &str = "restored_snapshot.json";
+
+fn to_jsonl<'a>(messages: impl Iterator<Item = &'a Message>) -> String {
+  let mut text = String::new();
+  for msg in messages {
+    text.push_str(&serde_json::to_string(msg).expect("JSON stringify error"));
+    text.push('\n');
+  }
+  text
+}
+
+fn append_entry(builder: &mut Builder<Vec<u8>>, path: String, data: &[u8], mtime: u64) {
+  let mut header = Header::new_gnu();
+  header.set_size(data.len() as u64);
+  header.set_mode(0o644);
+  header.set_mtime(mtime);
+  builder
+    .append_data(&mut header, path, data)
+    .expect("Failed to add snapshot entry");
+}
+
+// Creates a tar archive of all persistent queues
+// The queue map stays locked the whole time, so the archive is a consistent
+// point-in-time view and no compaction can run in between
+// Items are taken from memory, so the archive never contains a half-compacted file
+pub fn create_snapshot(broker: &Broker) -> Vec<u8> {
+  let queue_map = broker.queues();
+  let mut builder = Builder::new(Vec::new());
+  // Tar stores modification times in seconds
+  let now = timestamp() / 1000;
+
+  for queue in queue_map.values().filter(|queue| queue.is_persistent()) {
+    let name = queue.get_name();
+    debug!("Adding queue {} to snapshot", name);
+
+    let meta = serde_json::to_string(&queue.get_meta()).expect("JSON stringify error");
+    let items = to_jsonl(queue.items().iter());
+    let inflight = to_jsonl(queue.unacked_items().into_iter());
+
+    append_entry(
+      &mut builder,
+      format!("queues/{}/{}", name, META_FILE),
+      meta.as_bytes(),
+      now,
+    );
+    append_entry(
+      &mut builder,
+      format!("queues/{}/{}", name, ITEMS_FILE),
+      items.as_bytes(),
+      now,
+    );
+    append_entry(
+      &mut builder,
+      format!("queues/{}/{}", name, INFLIGHT_FILE),
+      inflight.as_bytes(),
+      now,
+    );
+  }
+
+  builder.into_inner().expect("Failed to finish snapshot")
+}
+
+// Splits "queues/{name}/{file}" into queue name & file name
+fn parse_entry_path(path: &Path) -> Option<(String, String)> {
+  let parts: Vec<String> = path
+    .components()
+    .map(|component| match component {
+      Component::Normal(part) => part.to_str().map(String::from),
+      _ => None,
+    })
+    .collect::<Option<Vec<String>>>()?;
+
+  if parts.len() != 3 || parts[0] != "queues" {
+    return None;
+  }
+  Some((parts[1].clone(), parts[2].clone()))
+}
+
+// Identifies an archive by its path, size & modification time
+fn snapshot_fingerprint(file: &str) -> Result<Value, String> {
+  let path =
+    canonicalize(file).map_err(|error| format!("Couldn't open snapshot file: {}", error))?;
+  let metadata = path
+    .metadata()
+    .map_err(|error| format!("Couldn't read snapshot file: {}", error))?;
+  let modified_at = metadata
+    .modified()
+    .ok()
+    .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+    .map_or(0, |duration| duration.as_millis() as u64);
+  Ok(json!({
+    "file": path.to_string_lossy(),
+    "size": metadata.len(),
+    "modified_at": modified_at,
+  }))
+}
+
+fn read_restored_marker(marker: &Path) -> Option<Value> {
+  let text = read_to_string(marker).ok()?;
+  serde_json::from_str(&text).ok()
+}
+
+// Reads the queue files of an archive, grouped by queue name
+fn read_archive(file: &str) -> Result<HashMap<String, HashMap<String, String>>, String> {
+  let archive_file =
+    File::open(file).map_err(|error| format!("Couldn't open snapshot file: {}", error))?;
+  let mut archive = Archive::new(archive_file);
+  let mut queues: HashMap<String, HashMap<String, String>> = HashMap::new();
+
+  let entries = archive
+    .entries()
+    .map_err(|error| format!("Couldn't read snapshot file: {}", error))?;
+  for entry in entries {
+    let mut entry = entry.map_err(|error| format!("Couldn't read snapshot entry: {}", error))?;
+    let path = entry
+      .path()
+      .map_err(|error| format!("Invalid snapshot entry path: {}", error))?
+      .into_owned();
+
+    if let Some((queue_name, file_name)) = parse_entry_path(&path) {
+      let mut content = String::new();
+      entry
+        .read_to_string(&mut content)
+        .map_err(|error| format!("Couldn't read snapshot entry {:?}: {}", path, error))?;
+      queues
+        .entry(queue_name)
+        .or_default()
+        .insert(file_name, content);
+    } else {
+      warn!("Unknown snapshot entry {:?}, skipping...", path);
+    }
+  }
+  Ok(queues)
+}
+
+// Rebuilds the queue folders from a snapshot archive & returns the number of restored queues
+// Queues contained in the snapshot are replaced, other queues are left untouched
+// Messages that were in-flight when the snapshot was taken are put back in front of the queue
+// The archive itself is left untouched, a marker file in the data folder records the restore,
+// so the same archive is restored once even if the setting stays in place across restarts
+pub fn restore_snapshot(broker: &Broker, file: &str) -> Result<usize, String> {
+  let marker = Path::new(broker.data_folder()).join(RESTORED_MARKER_FILE);
+  let fingerprint = snapshot_fingerprint(file)?;
+  if read_restored_marker(&marker).as_ref() == Some(&fingerprint) {
+    info!("Snapshot {} was already restored, skipping...", file);
+    return Ok(0);
+  }
+  info!("Restoring snapshot {}", file);
+  // Read the whole archive first, a broken one leaves the data folder untouched
+  let queues = read_archive(file)?;
+  broker.create_queues_folder();
+
+  let mut num_restored = 0;
+  for (queue_name, files) in queues.iter() {
+    let meta = match files.get(META_FILE) {
+      Some(meta) => meta,
+      None => {
+        warn!(
+          "Metadata of {} missing in snapshot, skipping...",
+          queue_name
+        );
+        continue;
+      }
+    };
+
+    info!("Restoring queue {}", queue_name);
+    let folder = broker.queue_folder(queue_name);
+    let write_queue = || -> std::io::Result<()> {
+      if Path::new(&folder).exists() {
+        remove_dir_all(&folder)?;
+      }
+      create_dir_all(&folder)?;
+
+      let mut items = String::new();
+      items.push_str(files.get(INFLIGHT_FILE).map_or("", String::as_str));
+      items.push_str(files.get(ITEMS_FILE).map_or("", String::as_str));
+
+      write(Path::new(&folder).join(META_FILE), meta)?;
+      if !items.is_empty() {
+        write(Path::new(&folder).join(ITEMS_FILE), items)?;
+      }
+      Ok(())
+    };
+    write_queue().map_err(|error| format!("Couldn't restore queue {}: {}", queue_name, error))?;
+    num_restored += 1;
+  }
+
+  // Only written once all queues are in place, an interrupted restore is repeated
+  write(&marker, fingerprint.to_string())
+    .map_err(|error| format!("Couldn't write {}: {}", RESTORED_MARKER_FILE, error))?;
+
+  info!("Restored {} queues from snapshot", num_restored);
+  Ok(num_restored)
+}
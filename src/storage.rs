@@ -0,0 +1,392 @@
+// Offline inspection & repair of persistent queues
+// Works directly on the files of a data folder, the server must not be running on it
+use crate::date::timestamp;
+use crate::queue::{
+  compact_file, get_queue_folder, initial_meta, is_valid_requeue_time, parse_metadata_file,
+  queue_item_file, queue_meta_backup_file, queue_meta_file, queue_temp_file, write_metadata,
+  Message, QueueMeta, QueueSettings, DELETED_FLAG, STORAGE_FORMAT_VERSION,
+};
+use fs2::FileExt;
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs::{copy, create_dir_all, read_dir, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+
+#[derive(Debug)]
+pub enum StorageError {
+  Io(io::Error),
+  QueueNotFound,
+  // Compacting would drop the corrupt records & everything after them
+  CorruptRecords,
+  // Truncating the bad tail would drop valid records, unless forced
+  ValidRecordsAfterCorruption { line: usize, num_valid: usize },
+  // Neither meta.json nor its backup can be read, unless forced
+  MetadataUnrecoverable,
+  // Another process (a running server) holds the data folder's lock
+  DataFolderLocked,
+}
+
+impl fmt::Display for StorageError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      StorageError::Io(error) => write!(f, "{}", error),
+      StorageError::QueueNotFound => write!(f, "Queue not found"),
+      StorageError::CorruptRecords => write!(f, "Item file contains corrupt records"),
+      StorageError::ValidRecordsAfterCorruption { line, num_valid } => write!(
+        f,
+        "{} valid records follow the corrupt record in line {}",
+        num_valid, line
+      ),
+      StorageError::MetadataUnrecoverable => {
+        write!(f, "Neither the metadata file nor its backup can be read")
+      }
+      StorageError::DataFolderLocked => write!(
+        f,
+        "Data folder is in use by another process, is the server running?"
+      ),
+    }
+  }
+}
+
+impl std::error::Error for StorageError {}
+
+impl From<io::Error> for StorageError {
+  fn from(error: io::Error) -> Self {
+    StorageError::Io(error)
+  }
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+pub enum MetaStatus {
+  Valid,
+  // meta.json is missing or corrupt, the server falls back to meta.json.bak
+  Backup,
+  Missing,
+  Corrupt,
+}
+
+#[derive(Serialize, Debug)]
+pub struct MetaReport {
+  pub status: MetaStatus,
+  // Metadata the server would use
+  pub meta: Option<QueueMeta>,
+  pub problems: Vec<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct CorruptRecord {
+  // Starting at 1
+  pub line: usize,
+  // Byte offset of the line in the item file
+  pub offset: u64,
+  pub reason: String,
+}
+
+#[derive(Serialize, Debug, Default)]
+pub struct ItemsReport {
+  pub exists: bool,
+  pub size: u64,
+  pub num_records: usize,
+  // Messages left after replaying the file
+  pub num_live: usize,
+  pub num_deleted: usize,
+  pub num_corrupt: usize,
+  // Deletion records without a message to delete
+  pub num_orphan_deletions: usize,
+  pub first_corrupt: Option<CorruptRecord>,
+  // Valid records following the first corrupt one, lost when truncating the file there
+  pub num_valid_after_corrupt: usize,
+}
+
+#[derive(Serialize, Debug)]
+pub struct QueueReport {
+  pub name: String,
+  pub meta: MetaReport,
+  pub items: ItemsReport,
+}
+
+impl QueueReport {
+  // Whether no problems were found
+  pub fn is_healthy(&self) -> bool {
+    self.meta.meta.is_some() && self.meta.problems.is_empty() && self.items.num_corrupt == 0
+  }
+}
+
+#[derive(Serialize, Debug, Default)]
+pub struct RepairReport {
+  // Bytes removed from the end of the item file
+  pub num_truncated_bytes: u64,
+  // Copy of the removed bytes, next to the item file
+  pub tail_file: Option<String>,
+  pub restored_meta_from_backup: bool,
+  pub recreated_meta: bool,
+}
+
+enum Record {
+  Message(Message),
+  Deletion,
+  Corrupt(String),
+}
+
+fn parse_record(line: &[u8]) -> Record {
+  let line = match std::str::from_utf8(line) {
+    Ok(line) => line,
+    Err(_) => return Record::Corrupt(String::from("Invalid UTF-8")),
+  };
+  if line.trim().is_empty() {
+    return Record::Corrupt(String::from("Empty line"));
+  }
+  let value: Value = match serde_json::from_str(line) {
+    Ok(value) => value,
+    Err(error) => return Record::Corrupt(format!("Invalid JSON: {}", error)),
+  };
+  if value[DELETED_FLAG].is_string() {
+    return Record::Deletion;
+  }
+  // The item is stored as stringified JSON object
+  let item_is_object = value["item"]
+    .as_str()
+    .and_then(|item| serde_json::from_str::<Value>(item).ok())
+    .is_some_and(|item| item.is_object());
+  match serde_json::from_value::<Message>(value) {
+    Ok(_) if !item_is_object => Record::Corrupt(String::from("Item is not a JSON object")),
+    Ok(message) => Record::Message(message),
+    Err(error) => Record::Corrupt(format!("Invalid message: {}", error)),
+  }
+}
+
+// Replays the item file like the server does, skipping corrupt records
+fn scan_items(path: &str) -> Result<(VecDeque<Message>, ItemsReport), StorageError> {
+  let mut items = VecDeque::new();
+  let mut report = ItemsReport::default();
+  if !Path::new(path).exists() {
+    return Ok((items, report));
+  }
+  report.exists = true;
+
+  let mut reader = BufReader::new(File::open(path)?);
+  let mut line = Vec::new();
+  let mut offset = 0;
+  loop {
+    line.clear();
+    let length = reader.read_until(b'\n', &mut line)?;
+    if length == 0 {
+      break;
+    }
+    report.num_records += 1;
+    let content = line.strip_suffix(b"\n").unwrap_or(&line);
+    match parse_record(content) {
+      Record::Message(message) => {
+        items.push_back(message);
+        if report.first_corrupt.is_some() {
+          report.num_valid_after_corrupt += 1;
+        }
+      }
+      Record::Deletion => {
+        report.num_deleted += 1;
+        if items.pop_front().is_none() {
+          report.num_orphan_deletions += 1;
+        }
+        if report.first_corrupt.is_some() {
+          report.num_valid_after_corrupt += 1;
+        }
+      }
+      Record::Corrupt(reason) => {
+        report.num_corrupt += 1;
+        if report.first_corrupt.is_none() {
+          report.first_corrupt = Some(CorruptRecord {
+            line: report.num_records,
+            offset,
+            reason,
+          });
+        }
+      }
+    }
+    offset += length as u64;
+  }
+  report.size = offset;
+  report.num_live = items.len();
+  Ok((items, report))
+}
+
+fn inspect_meta(folder: &str) -> MetaReport {
+  let meta_file = queue_meta_file(folder);
+  let backup_file = queue_meta_backup_file(folder);
+  let (status, meta) = match parse_metadata_file(&meta_file) {
+    Some(meta) => (MetaStatus::Valid, Some(meta)),
+    None => match parse_metadata_file(&backup_file) {
+      Some(meta) => (MetaStatus::Backup, Some(meta)),
+      None if Path::new(&meta_file).exists() || Path::new(&backup_file).exists() => {
+        (MetaStatus::Corrupt, None)
+      }
+      None => (MetaStatus::Missing, None),
+    },
+  };
+
+  let mut problems = Vec::new();
+  if status == MetaStatus::Backup {
+    problems.push(String::from(
+      "meta.json is missing or corrupt, the backup is used",
+    ));
+  }
+  match &meta {
+    Some(meta) => {
+      if meta.format_version > STORAGE_FORMAT_VERSION {
+        problems.push(format!(
+          "Written by a newer version (storage format {}, supported: {})",
+          meta.format_version, STORAGE_FORMAT_VERSION
+        ));
+      }
+      if !is_valid_requeue_time(meta.requeue_time) {
+        problems.push(format!("Invalid requeue time: {}", meta.requeue_time));
+      }
+    }
+    None => problems.push(String::from(
+      "No readable metadata, the queue is skipped on start",
+    )),
+  }
+  MetaReport {
+    status,
+    meta,
+    problems,
+  }
+}
+
+fn existing_queue_folder(data_folder: &str, name: &str) -> Result<String, StorageError> {
+  let folder = get_queue_folder(data_folder, name);
+  if Path::new(&folder).is_dir() {
+    Ok(folder)
+  } else {
+    Err(StorageError::QueueNotFound)
+  }
+}
+
+const LOCK_FILE: &str = "corinth.lock";
+
+// Exclusive lock of a data folder, released when dropped (or when the process exits)
+pub struct DataFolderLock {
+  _file: File,
+}
+
+// Locks the data folder, held by the server while it runs & while files are rewritten offline
+pub fn lock_data_folder(data_folder: &str) -> Result<DataFolderLock, StorageError> {
+  create_dir_all(data_folder)?;
+  let file = OpenOptions::new()
+    .write(true)
+    .create(true)
+    .truncate(false)
+    .open(Path::new(data_folder).join(LOCK_FILE))?;
+  if file.try_lock_exclusive().is_err() {
+    return Err(StorageError::DataFolderLocked);
+  }
+  Ok(DataFolderLock { _file: file })
+}
+
+// Names of the queues stored in the data folder, sorted
+pub fn stored_queues(data_folder: &str) -> Result<Vec<String>, StorageError> {
+  let folder = Path::new(data_folder).join("queues");
+  let mut names = Vec::new();
+  for entry in read_dir(folder)? {
+    let entry = entry?;
+    if entry.file_type()?.is_dir() {
+      names.push(entry.file_name().to_string_lossy().into_owned());
+    }
+  }
+  names.sort();
+  Ok(names)
+}
+
+pub fn inspect_queue(data_folder: &str, name: &str) -> Result<QueueReport, StorageError> {
+  let folder = existing_queue_folder(data_folder, name)?;
+  let (_, items) = scan_items(&queue_item_file(&folder, ""))?;
+  Ok(QueueReport {
+    name: String::from(name),
+    meta: inspect_meta(&folder),
+    items,
+  })
+}
+
+// Messages the queue contains, in queue order (corrupt records are skipped)
+pub fn read_queue_messages(data_folder: &str, name: &str) -> Result<Vec<Message>, StorageError> {
+  let folder = existing_queue_folder(data_folder, name)?;
+  let (items, _) = scan_items(&queue_item_file(&folder, ""))?;
+  Ok(items.into_iter().collect())
+}
+
+// Rewrites the item file with the live messages only
+// Returns the amount of kept messages
+pub fn compact_queue(data_folder: &str, name: &str) -> Result<usize, StorageError> {
+  let _lock = lock_data_folder(data_folder)?;
+  let folder = existing_queue_folder(data_folder, name)?;
+  let item_file = queue_item_file(&folder, "");
+  let (items, report) = scan_items(&item_file)?;
+  if report.num_corrupt > 0 {
+    return Err(StorageError::CorruptRecords);
+  }
+  if report.exists {
+    compact_file(&queue_temp_file(&folder), &item_file, &items);
+  }
+  Ok(items.len())
+}
+
+// Truncates the item file at its first corrupt record & restores unreadable metadata from its backup
+// The removed bytes are kept in a separate file
+// With force, valid records after the corruption are dropped as well
+// & missing metadata is recreated with default settings
+pub fn repair_queue(
+  data_folder: &str,
+  name: &str,
+  force: bool,
+) -> Result<RepairReport, StorageError> {
+  let _lock = lock_data_folder(data_folder)?;
+  let folder = existing_queue_folder(data_folder, name)?;
+  let item_file = queue_item_file(&folder, "");
+  let (_, items_report) = scan_items(&item_file)?;
+  let meta_report = inspect_meta(&folder);
+
+  if let Some(corrupt) = &items_report.first_corrupt {
+    if items_report.num_valid_after_corrupt > 0 && !force {
+      return Err(StorageError::ValidRecordsAfterCorruption {
+        line: corrupt.line,
+        num_valid: items_report.num_valid_after_corrupt,
+      });
+    }
+  }
+  if meta_report.meta.is_none() && !force {
+    return Err(StorageError::MetadataUnrecoverable);
+  }
+
+  let mut report = RepairReport::default();
+  if let Some(corrupt) = &items_report.first_corrupt {
+    report.tail_file = Some(save_tail(&item_file, corrupt.offset)?);
+    report.num_truncated_bytes = items_report.size - corrupt.offset;
+    let file = OpenOptions::new().write(true).open(&item_file)?;
+    file.set_len(corrupt.offset)?;
+    file.sync_all()?;
+  }
+
+  match meta_report.status {
+    MetaStatus::Valid => {}
+    MetaStatus::Backup => {
+      copy(queue_meta_backup_file(&folder), queue_meta_file(&folder))?;
+      report.restored_meta_from_backup = true;
+    }
+    MetaStatus::Missing | MetaStatus::Corrupt => {
+      write_metadata(&folder, &initial_meta(&QueueSettings::default()));
+      report.recreated_meta = true;
+    }
+  }
+  Ok(report)
+}
+
+// Copies the file's content from the offset on into a new file next to it
+fn save_tail(path: &str, offset: u64) -> Result<String, StorageError> {
+  let content = std::fs::read(path)?;
+  let tail_path = format!("{}.corrupt-{}", path, timestamp());
+  let mut tail_file = File::create(&tail_path)?;
+  tail_file.write_all(&content[offset as usize..])?;
+  tail_file.sync_all()?;
+  Ok(tail_path)
+}
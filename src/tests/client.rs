@@ -0,0 +1,161 @@
+use super::TestServer;
+use actix_rt::time::delay_for;
+use corinth_client::{Client, Consumer, CreateQueueOptions, NewMessage, RetryPolicy};
+use serde_json::json;
+use std::time::Duration;
+
+// Serves the test server's broker over HTTP, on a free port
+fn start_http(server: &TestServer) -> actix_web::test::TestServer {
+  let broker = server.broker.clone();
+  actix_web::test::start(move || {
+    actix_web::App::new()
+      .data(broker.clone())
+      .configure(crate::routes::configure_app)
+  })
+}
+
+fn volatile(requeue_time: f64) -> CreateQueueOptions {
+  CreateQueueOptions {
+    persistent: Some(false),
+    requeue_time: Some(requeue_time),
+    ..CreateQueueOptions::default()
+  }
+}
+
+#[actix_rt::test]
+async fn client_api() {
+  let server = TestServer::new();
+  let http = start_http(&server);
+  let client = Client::new(&http.url("/")).retry(RetryPolicy::none());
+
+  assert_eq!(client.info().await.unwrap().name, "Corinth");
+
+  client.create_queue("jobs", &volatile(300.0)).await.unwrap();
+  let error = client.create_queue("jobs", &volatile(300.0)).await;
+  assert_eq!(error.unwrap_err().status(), Some(409));
+  assert_eq!(
+    client.queue("missing").await.unwrap_err().status(),
+    Some(404)
+  );
+
+  let result = client
+    .enqueue(
+      "jobs",
+      vec![
+        NewMessage::deduplicated(json!({ "n": 1 }), "first"),
+        NewMessage::deduplicated(json!({ "n": 1 }), "first"),
+        NewMessage::new(json!({ "n": 2 })),
+      ],
+    )
+    .await
+    .unwrap();
+  assert_eq!(result.num_enqueued, 2);
+  assert_eq!(result.num_deduplicated, 1);
+  assert_eq!(client.list_queues().await.unwrap()[0].size, 2);
+  assert_eq!(
+    client.peek("jobs").await.unwrap().unwrap().item,
+    json!({ "n": 1 })
+  );
+
+  let exported = client.export("jobs", false).await.unwrap();
+  assert_eq!(exported.len(), 2);
+  let newest = exported
+    .iter()
+    .map(|message| message.queued_at)
+    .max()
+    .unwrap();
+  let since = client.export_since("jobs", newest).await.unwrap();
+  assert!(!since.is_empty());
+  assert!(since.iter().all(|message| message.queued_at >= newest));
+  assert!(client
+    .export_since("jobs", newest + 1)
+    .await
+    .unwrap()
+    .is_empty());
+  let full_copy = CreateQueueOptions {
+    max_length: Some(2),
+    ..volatile(300.0)
+  };
+  client.create_queue("copy", &full_copy).await.unwrap();
+  let imported = client.import("copy", &exported).await.unwrap();
+  assert_eq!(imported.num_imported, 2);
+  assert_eq!(client.queue("copy").await.unwrap().size, 2);
+  // Messages that are already in the full queue are skipped, not rejected
+  let imported = client.import("copy", &exported).await.unwrap();
+  assert_eq!(imported.num_imported, 0);
+  assert_eq!(imported.num_skipped, 2);
+
+  let messages = client.dequeue("jobs", 5, false).await.unwrap();
+  assert_eq!(messages.len(), 2);
+  let requeue_at = client
+    .extend("jobs", &messages[0].id, Some(600.0))
+    .await
+    .unwrap();
+  assert!(requeue_at > messages[0].queued_at + 500_000);
+  client.ack("jobs", &messages[0].id).await.unwrap();
+  let error = client.ack("jobs", &messages[0].id).await.unwrap_err();
+  assert_eq!(error.status(), Some(404));
+
+  assert!(client.metrics().await.unwrap().contains("corinth_queues 2"));
+
+  client.purge_queue("copy").await.unwrap();
+  client.delete_queue("copy").await.unwrap();
+  assert_eq!(client.list_queues().await.unwrap().len(), 1);
+}
+
+#[actix_rt::test]
+async fn queue_names_are_encoded() {
+  let server = TestServer::new();
+  let http = start_http(&server);
+  let client = Client::new(&http.url("/")).retry(RetryPolicy::none());
+
+  let name = "my jobs?#%1";
+  client.create_queue(name, &volatile(300.0)).await.unwrap();
+  assert_eq!(client.queue(name).await.unwrap().name, name);
+  assert_eq!(client.list_queues().await.unwrap()[0].name, name);
+
+  let messages = vec![NewMessage::new(json!({ "n": 1 }))];
+  client.enqueue(name, messages).await.unwrap();
+  let messages = client.dequeue(name, 1, false).await.unwrap();
+  client.extend(name, &messages[0].id, None).await.unwrap();
+  client.ack(name, &messages[0].id).await.unwrap();
+  assert_eq!(client.queue(name).await.unwrap().num_acknowledged, 1);
+  client.delete_queue(name).await.unwrap();
+}
+
+#[actix_rt::test]
+async fn consumer() {
+  let server = TestServer::new();
+  let http = start_http(&server);
+  let client = Client::new(&http.url("/")).retry(RetryPolicy::none());
+
+  client.create_queue("work", &volatile(1.0)).await.unwrap();
+  let messages = (1..=3)
+    .map(|n| NewMessage::new(json!({ "n": n })))
+    .collect();
+  client.enqueue("work", messages).await.unwrap();
+
+  let mut consumer = Consumer::new(client.clone(), "work")
+    .batch_size(2)
+    .extend_interval(Duration::from_millis(200));
+  let handler = |message: corinth_client::Message| async move {
+    match message.item["n"].as_u64() {
+      // Takes longer than the requeue time, so the lease has to be extended
+      Some(1) => {
+        delay_for(Duration::from_millis(1500)).await;
+        Ok(())
+      }
+      Some(2) => Err("failed"),
+      _ => Ok(()),
+    }
+  };
+
+  assert_eq!(consumer.process_batch(&handler).await.unwrap(), 2);
+  assert_eq!(consumer.process_batch(&handler).await.unwrap(), 1);
+
+  let queue = client.queue("work").await.unwrap();
+  assert_eq!(queue.num_acknowledged, 2);
+  assert_eq!(queue.num_requeued, 0);
+  // The failed message waits for its lease to run out
+  assert_eq!(queue.num_unacknowledged, 1);
+}
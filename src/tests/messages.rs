@@ -0,0 +1,251 @@
+use super::{messages, TestServer};
+use corinth::date::advance_clock;
+use serde_json::json;
+use std::time::Duration;
+
+#[actix_rt::test]
+async fn enqueue_dequeue_ack() {
+  let server = TestServer::new();
+  let mut app = app!(server);
+
+  send!(app, PUT, "/queue/work?persistent=false");
+  let body = messages(&[json!({ "n": 1 }), json!({ "n": 2 })]);
+  let (status, body) = send!(app, POST, "/queue/work/enqueue", body);
+  assert_eq!(status, 202);
+  assert_eq!(body["result"]["num_enqueued"], 2);
+  assert_eq!(body["result"]["num_deduplicated"], 0);
+  assert_eq!(body["result"]["items"][0]["state"], "Pending");
+
+  let (status, body) = send!(app, GET, "/queue/work/peek");
+  assert_eq!(status, 200);
+  assert_eq!(body["result"]["item"]["item"], json!({ "n": 1 }));
+
+  let (status, body) = send!(app, POST, "/queue/work/dequeue");
+  assert_eq!(status, 200);
+  assert_eq!(body["result"]["num_items"], 1);
+  let message = &body["result"]["items"][0];
+  assert_eq!(message["item"], json!({ "n": 1 }));
+  let message_id = message["id"].as_str().unwrap().to_string();
+
+  let (_, body) = send!(app, GET, "/queue/work");
+  assert_eq!(body["result"]["queue"]["size"], 1);
+  assert_eq!(body["result"]["queue"]["num_unacknowledged"], 1);
+
+  let (status, body) = send!(app, POST, format!("/queue/work/{}/ack", message_id));
+  assert_eq!(status, 200);
+  assert_eq!(body["message"], "Message reception acknowledged");
+
+  let (status, body) = send!(app, POST, format!("/queue/work/{}/ack", message_id));
+  assert_eq!(status, 404);
+  assert_eq!(body["message"], "Message not found");
+
+  let (_, body) = send!(app, GET, "/queue/work");
+  assert_eq!(body["result"]["queue"]["num_unacknowledged"], 0);
+  assert_eq!(body["result"]["queue"]["num_acknowledged"], 1);
+
+  // Auto acknowledged messages are not tracked
+  let (_, body) = send!(app, POST, "/queue/work/dequeue?ack=true");
+  assert_eq!(body["result"]["items"][0]["item"], json!({ "n": 2 }));
+  let (_, body) = send!(app, POST, "/queue/work/dequeue");
+  assert_eq!(body["result"]["num_items"], 0);
+  let (_, body) = send!(app, GET, "/queue/work");
+  assert_eq!(body["result"]["queue"]["size"], 0);
+  assert_eq!(body["result"]["queue"]["num_unacknowledged"], 0);
+}
+
+#[actix_rt::test]
+async fn enqueue_invalid() {
+  let server = TestServer::new();
+  let mut app = app!(server);
+
+  let (status, _) = send!(app, POST, "/queue/missing/enqueue", messages(&[json!({})]));
+  assert_eq!(status, 404);
+
+  send!(app, PUT, "/queue/strict?persistent=false");
+  let (status, _) = send!(
+    app,
+    POST,
+    "/queue/strict/enqueue",
+    messages(&[json!("text")])
+  );
+  assert_eq!(status, 400);
+  let (status, _) = send!(app, POST, "/queue/strict/dequeue?amount=0");
+  assert_eq!(status, 400);
+}
+
+#[actix_rt::test]
+async fn deduplication() {
+  let server = TestServer::new();
+  let mut app = app!(server);
+
+  send!(
+    app,
+    PUT,
+    "/queue/dedup?persistent=false&deduplication_time=10"
+  );
+  let body = json!({
+    "messages": [
+      { "item": { "n": 1 }, "deduplication_id": "same" },
+      { "item": { "n": 2 }, "deduplication_id": "same" },
+      { "item": { "n": 3 }, "deduplication_id": null },
+    ]
+  });
+  let (status, response) = send!(app, POST, "/queue/dedup/enqueue", body.clone());
+  assert_eq!(status, 202);
+  assert_eq!(response["result"]["num_enqueued"], 2);
+  assert_eq!(response["result"]["num_deduplicated"], 1);
+
+  let (_, response) = send!(app, GET, "/queue/dedup");
+  assert_eq!(response["result"]["queue"]["size"], 2);
+  assert_eq!(response["result"]["queue"]["num_deduplicating"], 1);
+  assert_eq!(response["result"]["queue"]["num_deduplicated"], 1);
+
+  advance_clock(Duration::from_secs(10));
+
+  let (_, response) = send!(app, GET, "/queue/dedup");
+  assert_eq!(response["result"]["queue"]["num_deduplicating"], 0);
+  let (_, response) = send!(app, POST, "/queue/dedup/enqueue", body);
+  assert_eq!(response["result"]["num_enqueued"], 2);
+  assert_eq!(response["result"]["num_deduplicated"], 1);
+}
+
+#[actix_rt::test]
+async fn requeue_unacknowledged() {
+  let server = TestServer::new();
+  let mut app = app!(server);
+
+  send!(app, PUT, "/queue/requeue?persistent=false&requeue_time=2");
+  send!(
+    app,
+    POST,
+    "/queue/requeue/enqueue",
+    messages(&[json!({ "n": 1 })])
+  );
+  let (_, body) = send!(app, POST, "/queue/requeue/dequeue");
+  assert_eq!(body["result"]["num_items"], 1);
+
+  advance_clock(Duration::from_millis(1500));
+  let (_, body) = send!(app, GET, "/queue/requeue");
+  assert_eq!(body["result"]["queue"]["size"], 0);
+  assert_eq!(body["result"]["queue"]["num_unacknowledged"], 1);
+
+  advance_clock(Duration::from_millis(500));
+  let (_, body) = send!(app, GET, "/queue/requeue");
+  assert_eq!(body["result"]["queue"]["size"], 1);
+  assert_eq!(body["result"]["queue"]["num_unacknowledged"], 0);
+  assert_eq!(body["result"]["queue"]["num_requeued"], 1);
+
+  let (_, body) = send!(app, POST, "/queue/requeue/dequeue");
+  let message = &body["result"]["items"][0];
+  assert_eq!(message["item"], json!({ "n": 1 }));
+  assert_eq!(message["num_requeues"], 1);
+  assert_eq!(message["state"], "Requeued");
+}
+
+#[actix_rt::test]
+async fn extend_lease() {
+  let server = TestServer::new();
+  let mut app = app!(server);
+
+  send!(app, PUT, "/queue/lease?persistent=false&requeue_time=2");
+  send!(
+    app,
+    POST,
+    "/queue/lease/enqueue",
+    messages(&[json!({ "n": 1 })])
+  );
+  let (_, body) = send!(app, POST, "/queue/lease/dequeue");
+  let id = body["result"]["items"][0]["id"]
+    .as_str()
+    .unwrap()
+    .to_string();
+
+  let (status, _) = send!(app, POST, format!("/queue/lease/{}/extend?seconds=0", id));
+  assert_eq!(status, 400);
+  let (status, _) = send!(app, POST, "/queue/lease/unknown/extend");
+  assert_eq!(status, 404);
+
+  advance_clock(Duration::from_millis(1500));
+  let (status, body) = send!(app, POST, format!("/queue/lease/{}/extend?seconds=2", id));
+  assert_eq!(status, 200);
+  assert!(body["result"]["requeue_at"].is_u64());
+
+  // The original deadline passes without requeuing the message
+  advance_clock(Duration::from_millis(1000));
+  let (_, body) = send!(app, GET, "/queue/lease");
+  assert_eq!(body["result"]["queue"]["num_unacknowledged"], 1);
+
+  advance_clock(Duration::from_millis(1000));
+  let (_, body) = send!(app, GET, "/queue/lease");
+  assert_eq!(body["result"]["queue"]["size"], 1);
+  assert_eq!(body["result"]["queue"]["num_unacknowledged"], 0);
+
+  let (status, _) = send!(app, POST, format!("/queue/lease/{}/extend", id));
+  assert_eq!(status, 404);
+}
+
+#[actix_rt::test]
+async fn dead_letter_queue() {
+  let server = TestServer::new();
+  let mut app = app!(server);
+
+  send!(app, PUT, "/queue/dead?persistent=false");
+  send!(
+    app,
+    PUT,
+    "/queue/failing?persistent=false&requeue_time=1&dead_letter_queue_name=dead&dead_letter_queue_threshold=1"
+  );
+  send!(
+    app,
+    POST,
+    "/queue/failing/enqueue",
+    messages(&[json!({ "n": 1 })])
+  );
+
+  // First expiration requeues, the next one moves the message into the dead letter queue
+  send!(app, POST, "/queue/failing/dequeue");
+  advance_clock(Duration::from_secs(1));
+  let (_, body) = send!(app, GET, "/queue/failing");
+  assert_eq!(body["result"]["queue"]["size"], 1);
+
+  send!(app, POST, "/queue/failing/dequeue");
+  advance_clock(Duration::from_secs(1));
+  let (_, body) = send!(app, GET, "/queue/failing");
+  assert_eq!(body["result"]["queue"]["size"], 0);
+  assert_eq!(body["result"]["queue"]["num_unacknowledged"], 0);
+
+  let (_, body) = send!(app, POST, "/queue/dead/dequeue?ack=true");
+  let message = &body["result"]["items"][0];
+  assert_eq!(message["item"], json!({ "n": 1 }));
+  assert_eq!(message["state"], "Failed");
+}
+
+#[actix_rt::test]
+async fn max_length() {
+  let server = TestServer::new();
+  let mut app = app!(server);
+
+  send!(app, PUT, "/queue/bounded?persistent=false&max_length=2");
+  let (status, _) = send!(
+    app,
+    POST,
+    "/queue/bounded/enqueue",
+    messages(&[json!({}), json!({}), json!({})])
+  );
+  assert_eq!(status, 403);
+
+  let (status, _) = send!(
+    app,
+    POST,
+    "/queue/bounded/enqueue",
+    messages(&[json!({}), json!({})])
+  );
+  assert_eq!(status, 202);
+  let (status, body) = send!(app, POST, "/queue/bounded/enqueue", messages(&[json!({})]));
+  assert_eq!(status, 403);
+  assert_eq!(body["message"], "Queue is full");
+
+  send!(app, POST, "/queue/bounded/dequeue?ack=true");
+  let (status, _) = send!(app, POST, "/queue/bounded/enqueue", messages(&[json!({})]));
+  assert_eq!(status, 202);
+}
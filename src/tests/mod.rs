@@ -0,0 +1,95 @@
+// Integration tests running the real route handlers against temporary data folders
+use crate::config::{set_config, Config};
+use crate::env::get_broker_options;
+use corinth::Broker;
+use lazy_static::lazy_static;
+use serde_json::{json, Value};
+use std::sync::{Mutex, MutexGuard};
+use tempfile::TempDir;
+
+// Creates the app with all routes, serving the test server's broker
+macro_rules! app {
+  ($server:expr) => {
+    actix_web::test::init_service(
+      actix_web::App::new()
+        .data($server.broker.clone())
+        .configure(crate::routes::configure_app),
+    )
+    .await
+  };
+}
+
+// Sends a request to the app, evaluates to the status & JSON body (null if the body is not JSON)
+macro_rules! send {
+  ($app:expr, $method:ident, $uri:expr) => {
+    send!($app, $method, $uri, serde_json::Value::Null)
+  };
+  ($app:expr, $method:ident, $uri:expr, $body:expr) => {{
+    let mut req = actix_web::test::TestRequest::default()
+      .method(actix_web::http::Method::$method)
+      .uri(&$uri);
+    let body: serde_json::Value = $body;
+    if !body.is_null() {
+      req = req.set_json(&body);
+    }
+    let res = actix_web::test::call_service(&mut $app, req.to_request()).await;
+    let status = res.status().as_u16();
+    let body = actix_web::test::read_body(res).await;
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap_or_default();
+    (status, json)
+  }};
+}
+
+mod client;
+mod messages;
+mod openapi;
+mod provision;
+mod queues;
+
+lazy_static! {
+  // Config & clock are global, so tests run one at a time
+  static ref SERIAL: Mutex<()> = Mutex::new(());
+}
+
+// Fresh server state backed by a temporary data folder, removed when dropped
+pub struct TestServer {
+  pub broker: Broker,
+  pub data_folder: TempDir,
+  _serial: MutexGuard<'static, ()>,
+}
+
+impl TestServer {
+  pub fn new() -> TestServer {
+    // A failed test poisons the lock, which must not fail the others
+    let serial = SERIAL.lock().unwrap_or_else(|error| error.into_inner());
+    let data_folder = tempfile::tempdir().expect("could not create temporary data folder");
+
+    let mut config = Config::default();
+    config.storage.data_folder = data_folder.path().to_str().unwrap().to_string();
+    config.storage.compact_interval = 0;
+    config.storage.meta_flush_interval = 0;
+    set_config(config);
+
+    TestServer {
+      broker: Broker::new(get_broker_options()),
+      data_folder,
+      _serial: serial,
+    }
+  }
+
+  // Simulates a graceful restart: flushes the queues, forgets them & reads them from disk
+  pub fn restart(&self) {
+    self.broker.flush();
+    self.broker.queues().clear();
+    self.broker.load();
+  }
+}
+
+// Enqueue body with one message per item, without deduplication IDs
+pub fn messages(items: &[Value]) -> Value {
+  let messages: Vec<Value> = items
+    .iter()
+    .map(|item| json!({ "item": item, "deduplication_id": null }))
+    .collect();
+  json!({ "messages": messages })
+}
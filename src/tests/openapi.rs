@@ -0,0 +1,114 @@
+use super::TestServer;
+use crate::openapi::openapi_json;
+use actix_web::http::{Method, StatusCode};
+use actix_web::test::{call_service, read_body, TestRequest};
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::env;
+use std::fs::{read_to_string, write};
+
+const DOCUMENT_FILE: &str = "docs/api/openapi.json";
+
+// Routes that are not part of the API
+const UNDOCUMENTED: &[(&str, &str)] = &[
+  ("GET", "/dashboard"),
+  ("GET", "/favicon.ico"),
+  ("GET", "/docs"),
+  ("GET", "/swagger-ui/{file}"),
+  ("POST", "/debug/clock/advance"),
+];
+
+const METHODS: &[&str] = &["GET", "PUT", "POST", "PATCH", "DELETE"];
+
+// Methods & paths among the given paths that the app built by configure_app answers
+// Unanswered requests get the default 404 without body, the handlers always send one
+async fn registered_routes(paths: &BTreeSet<String>) -> BTreeSet<(String, String)> {
+  let server = TestServer::new();
+  let mut app = app!(server);
+  let mut routes = BTreeSet::new();
+  for path in paths {
+    let uri: Vec<&str> = path
+      .split('/')
+      .map(|segment| {
+        if segment.starts_with('{') {
+          "probe"
+        } else {
+          segment
+        }
+      })
+      .collect();
+    for method in METHODS {
+      let req = TestRequest::default()
+        .method(Method::from_bytes(method.as_bytes()).unwrap())
+        .uri(&uri.join("/"))
+        .to_request();
+      let res = call_service(&mut app, req).await;
+      let status = res.status();
+      let body = read_body(res).await;
+      if status != StatusCode::NOT_FOUND || !body.is_empty() {
+        routes.insert((method.to_string(), path.clone()));
+      }
+    }
+  }
+  routes
+}
+
+fn documented_routes(document: &Value) -> BTreeSet<(String, String)> {
+  let mut routes = BTreeSet::new();
+  for (path, operations) in document["paths"].as_object().unwrap() {
+    for method in operations.as_object().unwrap().keys() {
+      routes.insert((method.to_uppercase(), path.clone()));
+    }
+  }
+  routes
+}
+
+#[actix_rt::test]
+async fn openapi_matches_routes() {
+  let document: Value = serde_json::from_str(&openapi_json()).unwrap();
+  let documented = documented_routes(&document);
+  let paths = documented
+    .iter()
+    .map(|(_, path)| path.clone())
+    .chain(UNDOCUMENTED.iter().map(|(_, path)| path.to_string()))
+    .collect();
+  let mut registered = registered_routes(&paths).await;
+  for (method, path) in UNDOCUMENTED {
+    assert!(
+      registered.remove(&(method.to_string(), path.to_string())),
+      "{} {} is not a route anymore",
+      method,
+      path
+    );
+  }
+  assert_eq!(registered, documented);
+}
+
+// Regenerate with CORINTH_UPDATE_OPENAPI=1 cargo test openapi
+#[test]
+fn openapi_document_is_up_to_date() {
+  let document = openapi_json() + "\n";
+  if env::var("CORINTH_UPDATE_OPENAPI").is_ok() {
+    write(DOCUMENT_FILE, &document).unwrap();
+  }
+  let stored = read_to_string(DOCUMENT_FILE).unwrap_or_default();
+  assert!(
+    stored == document,
+    "{} is outdated, regenerate it with CORINTH_UPDATE_OPENAPI=1 cargo test openapi",
+    DOCUMENT_FILE
+  );
+}
+
+#[actix_rt::test]
+async fn serve_openapi_document() {
+  let server = TestServer::new();
+  let mut app = app!(server);
+
+  let (status, body) = send!(app, GET, "/openapi.json");
+  assert_eq!(status, 200);
+  assert!(body["openapi"].as_str().unwrap().starts_with("3."));
+  assert_eq!(body["info"]["version"], env!("CARGO_PKG_VERSION"));
+
+  let (status, _) = send!(app, GET, "/docs");
+  assert_eq!(status, 200);
+}
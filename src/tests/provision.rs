@@ -0,0 +1,119 @@
+use super::TestServer;
+use crate::config::{config, set_config, QueueDeclaration};
+use crate::provision::provision_queues;
+use serde_json::json;
+
+fn declaration(name: &str) -> QueueDeclaration {
+  QueueDeclaration {
+    name: String::from(name),
+    persistent: Some(false),
+    requeue_time: None,
+    deduplication_time: None,
+    max_length: None,
+    dead_letter_queue: None,
+    dead_letter_threshold: None,
+  }
+}
+
+fn declare(queues: Vec<QueueDeclaration>) {
+  let mut declared = config().clone();
+  declared.queues = queues;
+  set_config(declared);
+}
+
+#[actix_rt::test]
+async fn create_declared_queues() {
+  let server = TestServer::new();
+  let mut app = app!(server);
+
+  declare(vec![
+    QueueDeclaration {
+      max_length: Some(10),
+      dead_letter_queue: Some(String::from("failed")),
+      dead_letter_threshold: Some(2),
+      ..declaration("jobs")
+    },
+    declaration("failed"),
+  ]);
+  provision_queues(&server.broker).unwrap();
+
+  let (status, body) = send!(app, GET, "/queue/jobs");
+  assert_eq!(status, 200);
+  let queue = &body["result"]["queue"];
+  assert_eq!(queue["max_length"], 10);
+  assert_eq!(queue["persistent"], false);
+  // Unset settings use the queue defaults
+  assert_eq!(queue["requeue_time"], 300.0);
+  assert_eq!(
+    queue["dead_letter"],
+    json!({ "name": "failed", "threshold": 2 })
+  );
+  let (status, _) = send!(app, GET, "/queue/failed");
+  assert_eq!(status, 200);
+
+  // Provisioning again leaves the queues as they are
+  provision_queues(&server.broker).unwrap();
+  let (_, body) = send!(app, GET, "/queues");
+  assert_eq!(body["result"]["queues"]["length"], 2);
+}
+
+#[actix_rt::test]
+async fn overwrite_drifted_settings() {
+  let server = TestServer::new();
+  let mut app = app!(server);
+
+  send!(app, PUT, "/queue/dlq?persistent=false");
+  send!(
+    app,
+    PUT,
+    "/queue/jobs?persistent=false&requeue_time=5&deduplication_time=20&max_length=3"
+  );
+
+  declare(vec![QueueDeclaration {
+    persistent: Some(true),
+    requeue_time: Some(10.0),
+    max_length: Some(3),
+    dead_letter_queue: Some(String::from("dlq")),
+    ..declaration("jobs")
+  }]);
+  provision_queues(&server.broker).unwrap();
+
+  let (_, body) = send!(app, GET, "/queue/jobs");
+  let queue = &body["result"]["queue"];
+  assert_eq!(queue["requeue_time"], 10.0);
+  assert_eq!(queue["max_length"], 3);
+  assert_eq!(
+    queue["dead_letter"],
+    json!({ "name": "dlq", "threshold": 3 })
+  );
+  // Undeclared settings are not reconciled & persistence cannot be changed
+  assert_eq!(queue["deduplication_time"], 20);
+  assert_eq!(queue["persistent"], false);
+}
+
+#[actix_rt::test]
+async fn undeclared_dead_letter_queue() {
+  let server = TestServer::new();
+  let mut app = app!(server);
+
+  declare(vec![
+    declaration("other"),
+    QueueDeclaration {
+      dead_letter_queue: Some(String::from("missing")),
+      ..declaration("jobs")
+    },
+  ]);
+  assert_eq!(
+    provision_queues(&server.broker).unwrap_err(),
+    "Dead letter queue missing of queue jobs not found"
+  );
+  // Nothing is created if the config is inconsistent
+  let (_, body) = send!(app, GET, "/queues");
+  assert_eq!(body["result"]["queues"]["length"], 0);
+
+  // Existing queues may be used without declaring them
+  send!(app, PUT, "/queue/missing?persistent=false");
+  provision_queues(&server.broker).unwrap();
+  let (_, body) = send!(app, GET, "/queue/jobs");
+  assert_eq!(body["result"]["queue"]["dead_letter"]["name"], "missing");
+}
@@ -0,0 +1,197 @@
+use super::{messages, TestServer};
+use serde_json::json;
+
+#[actix_rt::test]
+async fn server_info() {
+  let server = TestServer::new();
+  let mut app = app!(server);
+
+  let (status, body) = send!(app, GET, "/");
+  assert_eq!(status, 200);
+  assert_eq!(body["result"]["info"]["name"], "Corinth");
+}
+
+#[actix_rt::test]
+async fn create_queue() {
+  let server = TestServer::new();
+  let mut app = app!(server);
+
+  let (status, body) = send!(
+    app,
+    PUT,
+    "/queue/created?persistent=false&requeue_time=0.5&deduplication_time=10&max_length=5"
+  );
+  assert_eq!(status, 201);
+  assert_eq!(body["message"], "Queue created successfully");
+
+  let (status, body) = send!(app, GET, "/queue/created");
+  assert_eq!(status, 200);
+  let queue = &body["result"]["queue"];
+  assert_eq!(queue["name"], "created");
+  assert_eq!(queue["size"], 0);
+  assert_eq!(queue["requeue_time"], 0.5);
+  assert_eq!(queue["deduplication_time"], 10);
+  assert_eq!(queue["max_length"], 5);
+  assert_eq!(queue["persistent"], false);
+
+  let (status, body) = send!(app, PUT, "/queue/created");
+  assert_eq!(status, 409);
+  assert_eq!(body["message"], "Queue already exists");
+
+  let (status, body) = send!(app, GET, "/queues");
+  assert_eq!(status, 200);
+  assert_eq!(body["result"]["queues"]["length"], 1);
+  assert_eq!(body["result"]["queues"]["items"][0]["name"], "created");
+}
+
+#[actix_rt::test]
+async fn create_queue_invalid_arguments() {
+  let server = TestServer::new();
+  let mut app = app!(server);
+
+  for query in &[
+    "requeue_time=-1",
+    "requeue_time=abc",
+    "deduplication_time=-5",
+  ] {
+    let (status, body) = send!(app, PUT, format!("/queue/invalid?{}", query));
+    assert_eq!(status, 400, "{}", query);
+    assert_eq!(body["message"], "Invalid time argument");
+  }
+
+  let (status, _) = send!(app, GET, "/queue/invalid");
+  assert_eq!(status, 404);
+
+  let (status, body) = send!(app, PUT, "/queue/with_dlq?dead_letter_queue_name=missing");
+  assert_eq!(status, 404);
+  assert_eq!(body["message"], "Dead letter target not found");
+}
+
+#[actix_rt::test]
+async fn edit_queue() {
+  let server = TestServer::new();
+  let mut app = app!(server);
+
+  send!(app, PUT, "/queue/edited?persistent=false");
+  let body = json!({ "requeue_time": 2.5, "deduplication_time": 60, "max_length": 1 });
+  let (status, body) = send!(app, PATCH, "/queue/edited", body);
+  assert_eq!(status, 200);
+  assert_eq!(body["message"], "Queue edited successfully");
+
+  let (_, body) = send!(app, GET, "/queue/edited");
+  let queue = &body["result"]["queue"];
+  assert_eq!(queue["requeue_time"], 2.5);
+  assert_eq!(queue["deduplication_time"], 60);
+  assert_eq!(queue["max_length"], 1);
+
+  let body = messages(&[json!({ "n": 1 }), json!({ "n": 2 })]);
+  let (status, _) = send!(app, POST, "/queue/edited/enqueue", body);
+  assert_eq!(status, 403);
+}
+
+#[actix_rt::test]
+async fn purge_queue() {
+  let server = TestServer::new();
+  let mut app = app!(server);
+
+  send!(app, PUT, "/queue/purged?persistent=false");
+  let body = messages(&[json!({ "n": 1 }), json!({ "n": 2 })]);
+  let (status, _) = send!(app, POST, "/queue/purged/enqueue", body);
+  assert_eq!(status, 202);
+  let (status, _) = send!(app, POST, "/queue/purged/dequeue");
+  assert_eq!(status, 200);
+
+  let (status, body) = send!(app, DELETE, "/queue/purged/purge");
+  assert_eq!(status, 200);
+  assert_eq!(body["message"], "Queue purged successfully");
+
+  let (_, body) = send!(app, GET, "/queue/purged");
+  assert_eq!(body["result"]["queue"]["size"], 0);
+  assert_eq!(body["result"]["queue"]["num_unacknowledged"], 0);
+  // Exported counters are reset together
+  let meta = server.broker.queues()["purged"].get_meta();
+  assert_eq!(meta.num_enqueued, 0);
+  assert_eq!(meta.num_dequeued, 0);
+
+  let (status, _) = send!(app, DELETE, "/queue/missing/purge");
+  assert_eq!(status, 404);
+}
+
+#[actix_rt::test]
+async fn delete_queue() {
+  let server = TestServer::new();
+  let mut app = app!(server);
+
+  send!(app, PUT, "/queue/deleted?persistent=true");
+  let (status, _) = send!(app, POST, "/queue/deleted/enqueue", messages(&[json!({})]));
+  assert_eq!(status, 202);
+  let queue_folder = server.data_folder.path().join("queues").join("deleted");
+  assert!(queue_folder.exists());
+
+  let (status, body) = send!(app, DELETE, "/queue/deleted");
+  assert_eq!(status, 200);
+  assert_eq!(body["message"], "Queue deleted successfully");
+  assert!(!queue_folder.exists());
+
+  let (status, _) = send!(app, GET, "/queue/deleted");
+  assert_eq!(status, 404);
+  let (status, _) = send!(app, DELETE, "/queue/deleted");
+  assert_eq!(status, 404);
+}
+
+#[actix_rt::test]
+async fn delete_dead_letter_queue_in_use() {
+  let server = TestServer::new();
+  let mut app = app!(server);
+
+  send!(app, PUT, "/queue/dlq_target?persistent=false");
+  let (status, _) = send!(
+    app,
+    PUT,
+    "/queue/dlq_source?persistent=false&dead_letter_queue_name=dlq_target"
+  );
+  assert_eq!(status, 201);
+
+  let (status, body) = send!(app, DELETE, "/queue/dlq_target");
+  assert_eq!(status, 403);
+  assert_eq!(body["message"], "Dead letter queue is in use");
+
+  let (status, _) = send!(app, DELETE, "/queue/dlq_source");
+  assert_eq!(status, 200);
+  let (status, _) = send!(app, DELETE, "/queue/dlq_target");
+  assert_eq!(status, 200);
+}
+
+#[actix_rt::test]
+async fn persistent_queue_reload() {
+  let server = TestServer::new();
+  let mut app = app!(server);
+
+  send!(app, PUT, "/queue/stored?persistent=true");
+  send!(app, PUT, "/queue/volatile?persistent=false");
+  let body = messages(&[json!({ "n": 1 }), json!({ "n": 2 }), json!({ "n": 3 })]);
+  let (status, _) = send!(app, POST, "/queue/stored/enqueue", body.clone());
+  assert_eq!(status, 202);
+  let (status, _) = send!(app, POST, "/queue/volatile/enqueue", body);
+  assert_eq!(status, 202);
+  let (status, body) = send!(app, POST, "/queue/stored/dequeue?ack=true");
+  assert_eq!(status, 200);
+  assert_eq!(body["result"]["items"][0]["item"], json!({ "n": 1 }));
+
+  server.restart();
+
+  let (status, body) = send!(app, GET, "/queue/stored");
+  assert_eq!(status, 200);
+  assert_eq!(body["result"]["queue"]["size"], 2);
+  assert_eq!(body["result"]["queue"]["num_acknowledged"], 1);
+  assert_eq!(body["result"]["queue"]["persistent"], true);
+
+  let (_, body) = send!(app, POST, "/queue/stored/dequeue?ack=true&amount=2");
+  let items = &body["result"]["items"];
+  assert_eq!(items[0]["item"], json!({ "n": 2 }));
+  assert_eq!(items[1]["item"], json!({ "n": 3 }));
+
+  // Volatile queues only live in memory
+  let (status, _) = send!(app, GET, "/queue/volatile");
+  assert_eq!(status, 404);
+}
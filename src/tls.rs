@@ -0,0 +1,123 @@
+use crate::env::{get_tls_cert, get_tls_client_ca, get_tls_key};
+use lazy_static::lazy_static;
+use log::{error, info};
+use rustls::internal::pemfile::{certs, pkcs8_private_keys, rsa_private_keys};
+use rustls::sign::{any_supported_type, CertifiedKey};
+use rustls::{
+  AllowAnyAuthenticatedClient, ClientHello, NoClientAuth, ResolvesServerCert, RootCertStore,
+  ServerConfig,
+};
+use std::fs::File;
+use std::io::BufReader;
+use std::sync::{Arc, Mutex, RwLock};
+
+// Hands out the currently loaded certificate, so it can be swapped without restarting
+struct ReloadableCert {
+  cert_path: String,
+  key_path: String,
+  current: RwLock<CertifiedKey>,
+}
+
+impl ReloadableCert {
+  fn reload(&self) -> Result<(), String> {
+    let certified_key = load_certified_key(&self.cert_path, &self.key_path)?;
+    *self.current.write().unwrap() = certified_key;
+    Ok(())
+  }
+}
+
+impl ResolvesServerCert for ReloadableCert {
+  fn resolve(&self, _client_hello: ClientHello) -> Option<CertifiedKey> {
+    Some(self.current.read().unwrap().clone())
+  }
+}
+
+lazy_static! {
+  static ref CERT: Mutex<Option<Arc<ReloadableCert>>> = Mutex::new(None);
+}
+
+fn open_pem(path: &str) -> Result<BufReader<File>, String> {
+  File::open(path)
+    .map(BufReader::new)
+    .map_err(|error| format!("Could not open {}: {}", path, error))
+}
+
+fn load_certified_key(cert_path: &str, key_path: &str) -> Result<CertifiedKey, String> {
+  let chain = certs(&mut open_pem(cert_path)?).unwrap_or_default();
+  if chain.is_empty() {
+    return Err(format!("No certificate found in {}", cert_path));
+  }
+
+  // Accept both PKCS#8 and PKCS#1 (RSA) keys
+  let mut keys = pkcs8_private_keys(&mut open_pem(key_path)?).unwrap_or_default();
+  if keys.is_empty() {
+    keys = rsa_private_keys(&mut open_pem(key_path)?).unwrap_or_default();
+  }
+  let key = keys
+    .first()
+    .ok_or(format!("No private key found in {}", key_path))?;
+  let signing_key =
+    any_supported_type(key).map_err(|_| format!("Unsupported private key in {}", key_path))?;
+
+  Ok(CertifiedKey::new(chain, Arc::new(signing_key)))
+}
+
+fn load_client_roots(path: &str) -> Result<RootCertStore, String> {
+  let mut roots = RootCertStore::empty();
+  match roots.add_pem_file(&mut open_pem(path)?) {
+    Ok((num_added, _)) if num_added > 0 => Ok(roots),
+    _ => Err(format!("No valid CA certificate found in {}", path)),
+  }
+}
+
+// Builds the TLS configuration if a certificate is configured
+// Clients have to present a certificate signed by the client CA, if set
+pub fn tls_config() -> Result<Option<ServerConfig>, String> {
+  let (cert_path, key_path) = match (get_tls_cert(), get_tls_key()) {
+    (Some(cert_path), Some(key_path)) => (cert_path, key_path),
+    _ => return Ok(None),
+  };
+
+  let certified_key = load_certified_key(&cert_path, &key_path)?;
+  let client_verifier = match get_tls_client_ca() {
+    Some(ca_path) => AllowAnyAuthenticatedClient::new(load_client_roots(&ca_path)?),
+    None => NoClientAuth::new(),
+  };
+
+  let resolver = Arc::new(ReloadableCert {
+    cert_path,
+    key_path,
+    current: RwLock::new(certified_key),
+  });
+  *CERT.lock().unwrap() = Some(resolver.clone());
+
+  let mut config = ServerConfig::new(client_verifier);
+  config.cert_resolver = resolver;
+  Ok(Some(config))
+}
+
+// Re-reads the certificate & key files, new connections use the new certificate
+// Keeps the old certificate if the files are invalid
+pub fn reload_certificate() {
+  let cert = CERT.lock().unwrap().clone();
+  if let Some(cert) = cert {
+    match cert.reload() {
+      Ok(()) => info!("Reloaded TLS certificate from {}", cert.cert_path),
+      Err(error) => error!("Failed to reload TLS certificate: {}", error),
+    }
+  }
+}
+
+// Reloads the certificate on SIGHUP
+#[cfg(unix)]
+pub async fn listen_for_reload() {
+  use actix_web::rt::signal::unix::{signal, SignalKind};
+
+  let mut hangup = signal(SignalKind::hangup()).expect("Failed to listen for SIGHUP");
+  while hangup.recv().await.is_some() {
+    reload_certificate();
+  }
+}
+
+#[cfg(not(unix))]
+pub async fn listen_for_reload() {}
@@ -76,3 +76,12 @@ export async function dequeue<T>(
   });
   return res.data.result.items;
 }
+
+// Moves the server clock forward (debug builds only)
+// Resolves once all timers that became due (requeues, dedup expiration...) have run
+export async function advanceClock(ms: number): Promise<number> {
+  const { data } = await Axios.post(getUrl("/debug/clock/advance"), null, {
+    params: { ms },
+  });
+  return data.result.now;
+}
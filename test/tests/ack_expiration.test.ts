@@ -1,12 +1,13 @@
 import { defineWorkflow } from "voce";
 import {
+  advanceClock,
   createQueue,
   enqueue,
   Message,
   MessageState,
   queueUri,
 } from "../common";
-import { IP, sleep } from "../util";
+import { IP } from "../util";
 import yxc from "@dotvirus/yxc";
 
 export default defineWorkflow(async () => {
@@ -100,7 +101,7 @@ export default defineWorkflow(async () => {
             }),
           }),
         }),
-        onSuccess: () => sleep(3000),
+        onSuccess: () => advanceClock(3000),
       },
       {
         title: "1 item should be queued again",
@@ -0,0 +1,123 @@
+import { defineWorkflow } from "voce";
+import { queueUrl } from "../common";
+import { getUrl, IP, NO_FAIL, PORT, sleep, spawnCorinth } from "../util";
+import { expect } from "chai";
+import Axios from "axios";
+
+const ADMIN_KEY = "admin-key";
+const TEAM_ADMIN_KEY = "billing-admin";
+const TEAM_KEY = "billing-worker";
+
+const bearer = (key: string) => ({
+  ...NO_FAIL(),
+  headers: { Authorization: `Bearer ${key}` },
+});
+
+async function restartCorinth(env: Record<string, string> = {}) {
+  await Axios.post(getUrl("/close"), null, bearer(ADMIN_KEY));
+  await sleep(3500);
+  await spawnCorinth(PORT, 0, env);
+}
+
+export default defineWorkflow(async () => {
+  const teamQueue = "billing-acl_test";
+  const otherQueue = "acl_test";
+
+  await restartCorinth({
+    CORINTH_ADMIN_KEYS: `${ADMIN_KEY},${TEAM_ADMIN_KEY}`,
+    CORINTH_DATA_KEYS: TEAM_KEY,
+    CORINTH_ACL: `${TEAM_KEY}:produce+consume:billing-*,${TEAM_ADMIN_KEY}:admin:billing-*`,
+  });
+
+  return {
+    title: "Queue access control",
+    baseUrl: IP,
+    steps: [
+      {
+        title: "Restricted admin key may only create matching queues",
+        status: 200,
+        url: "/",
+        validate: async () => {
+          const created = await Axios.put(
+            queueUrl(teamQueue),
+            null,
+            bearer(TEAM_ADMIN_KEY)
+          );
+          expect(created.status).to.equal(201);
+
+          const forbidden = await Axios.put(
+            queueUrl(otherQueue),
+            null,
+            bearer(TEAM_ADMIN_KEY)
+          );
+          expect(forbidden.status).to.equal(403);
+          expect(forbidden.data.message).to.equal(
+            "API key is not allowed to access this queue"
+          );
+
+          const other = await Axios.put(
+            queueUrl(otherQueue),
+            null,
+            bearer(ADMIN_KEY)
+          );
+          expect(other.status).to.equal(201);
+
+          const snapshot = await Axios.get(
+            getUrl("/snapshot"),
+            bearer(TEAM_ADMIN_KEY)
+          );
+          expect(snapshot.status).to.equal(403);
+        },
+      },
+      {
+        title: "Restricted data key may only use matching queues",
+        status: 200,
+        url: "/",
+        validate: async () => {
+          const message = {
+            messages: [{ item: { index: 0 }, deduplication_id: null }],
+          };
+
+          const enqueued = await Axios.post(
+            queueUrl(teamQueue) + "/enqueue",
+            message,
+            bearer(TEAM_KEY)
+          );
+          expect(enqueued.status).to.equal(202);
+
+          const forbidden = await Axios.post(
+            queueUrl(otherQueue) + "/enqueue",
+            message,
+            bearer(TEAM_KEY)
+          );
+          expect(forbidden.status).to.equal(403);
+
+          const info = await Axios.get(queueUrl(otherQueue), bearer(TEAM_KEY));
+          expect(info.status).to.equal(403);
+        },
+      },
+      {
+        title: "Queue list only shows visible queues",
+        status: 200,
+        url: "/",
+        validate: async () => {
+          const teamList = await Axios.get(getUrl("/queues"), bearer(TEAM_KEY));
+          expect(
+            teamList.data.result.queues.items.map((q: any) => q.name)
+          ).to.deep.equal([teamQueue]);
+
+          const fullList = await Axios.get(
+            getUrl("/queues"),
+            bearer(ADMIN_KEY)
+          );
+          expect(fullList.data.result.queues.length).to.be.at.least(2);
+        },
+        onSuccess: async () => {
+          await Axios.delete(queueUrl(teamQueue), bearer(ADMIN_KEY));
+          await Axios.delete(queueUrl(otherQueue), bearer(ADMIN_KEY));
+          await restartCorinth();
+        },
+      },
+    ],
+  };
+});
@@ -0,0 +1,103 @@
+import { defineWorkflow } from "voce";
+import { queueUri, queueUrl } from "../common";
+import { getUrl, IP, NO_FAIL, PORT, sleep, spawnCorinth } from "../util";
+import yxc from "@dotvirus/yxc";
+import { expect } from "chai";
+import Axios from "axios";
+
+const ADMIN_KEY = "admin-key";
+const DATA_KEY = "data-key";
+
+const bearer = (key: string) => ({
+  ...NO_FAIL(),
+  headers: { Authorization: `Bearer ${key}` },
+});
+
+async function restartCorinth(env: Record<string, string> = {}) {
+  await Axios.post(getUrl("/close"), null, bearer(ADMIN_KEY));
+  await sleep(3500);
+  await spawnCorinth(PORT, 0, env);
+}
+
+export default defineWorkflow(async () => {
+  const queueName = "auth_test";
+
+  await restartCorinth({
+    CORINTH_ADMIN_KEYS: ADMIN_KEY,
+    CORINTH_DATA_KEYS: DATA_KEY,
+  });
+
+  return {
+    title: "Authentication",
+    baseUrl: IP,
+    steps: [
+      {
+        title: "Server info is public",
+        status: 200,
+        url: "/",
+      },
+      {
+        title: "Create queue without key -> 401",
+        status: 401,
+        url: queueUri(queueName),
+        method: "PUT",
+        resBody: yxc.object({
+          error: yxc.boolean().true(),
+          message: yxc.string().equals("Missing or invalid API key"),
+          status: yxc.number().equals(401),
+        }),
+      },
+      {
+        title: "Data key may not use admin routes",
+        status: 401,
+        url: queueUri(queueName),
+        validate: async () => {
+          const forbidden = await Axios.put(
+            queueUrl(queueName),
+            null,
+            bearer(DATA_KEY)
+          );
+          expect(forbidden.status).to.equal(403);
+
+          const created = await Axios.put(
+            queueUrl(queueName),
+            null,
+            bearer(ADMIN_KEY)
+          );
+          expect(created.status).to.equal(201);
+        },
+      },
+      {
+        title: "Data key may produce & consume",
+        status: 401,
+        url: queueUri(queueName) + "/peek",
+        validate: async () => {
+          const enqueued = await Axios.post(
+            queueUrl(queueName) + "/enqueue",
+            { messages: [{ item: { index: 0 }, deduplication_id: null }] },
+            bearer(DATA_KEY)
+          );
+          expect(enqueued.status).to.equal(202);
+
+          const dequeued = await Axios.post(
+            queueUrl(queueName) + "/dequeue?ack=true",
+            null,
+            bearer(DATA_KEY)
+          );
+          expect(dequeued.status).to.equal(200);
+          expect(dequeued.data.result.num_items).to.equal(1);
+
+          const wrongKey = await Axios.get(
+            queueUrl(queueName),
+            bearer("wrong-key")
+          );
+          expect(wrongKey.status).to.equal(401);
+        },
+        onSuccess: async () => {
+          await Axios.delete(queueUrl(queueName), bearer(ADMIN_KEY));
+          await restartCorinth();
+        },
+      },
+    ],
+  };
+});
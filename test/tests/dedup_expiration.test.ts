@@ -1,6 +1,12 @@
 import { defineWorkflow } from "voce";
-import { IP, sleep } from "../util";
-import { queueUri, createQueue, Message, enqueue } from "../common";
+import { IP } from "../util";
+import {
+  advanceClock,
+  queueUri,
+  createQueue,
+  Message,
+  enqueue,
+} from "../common";
 import yxc from "@dotvirus/yxc";
 
 export default defineWorkflow(async () => {
@@ -73,7 +79,7 @@ export default defineWorkflow(async () => {
           }),
         }),
         onSuccess: async () => {
-          await sleep(3000);
+          await advanceClock(3000);
         },
       },
       {
@@ -0,0 +1,117 @@
+import { defineWorkflow } from "voce";
+import { createQueue, dequeue, enqueue, Message, queueUri } from "../common";
+import { getUrl, IP } from "../util";
+import yxc from "@dotvirus/yxc";
+import { expect } from "chai";
+import Axios from "axios";
+
+export default defineWorkflow(async () => {
+  const sourceName = "export_source";
+  const targetName = "export_target";
+
+  await createQueue(sourceName);
+  await createQueue(targetName);
+
+  await enqueue(sourceName, [
+    { item: { index: 0 }, deduplication_id: null },
+    { item: { index: 1 }, deduplication_id: null },
+    { item: { index: 2 }, deduplication_id: null },
+  ]);
+  await dequeue(sourceName);
+
+  const exportUrl = getUrl(queueUri(sourceName) + "/export");
+  const waiting = (await Axios.get(exportUrl, { responseType: "text" })).data;
+  const all = (
+    await Axios.get(exportUrl, {
+      params: { include_in_flight: "true" },
+      responseType: "text",
+    })
+  ).data;
+
+  const parseLines = (text: string) =>
+    text
+      .split("\n")
+      .filter(Boolean)
+      .map((line) => JSON.parse(line));
+
+  return {
+    title: "Export/Import",
+    baseUrl: IP,
+    steps: [
+      {
+        title: "Export should contain waiting messages",
+        status: 200,
+        url: queueUri(sourceName),
+        validate: () => {
+          const messages = parseLines(waiting);
+          expect(messages.map((msg) => msg.item.index)).to.deep.equal([1, 2]);
+          const allMessages = parseLines(all);
+          expect(allMessages.map((msg) => msg.item.index)).to.deep.equal([
+            0, 1, 2,
+          ]);
+          for (const msg of allMessages) {
+            expect(Message().validate(msg)).to.be.empty;
+          }
+        },
+      },
+      {
+        title: "Import messages",
+        status: 202,
+        url: queueUri(targetName) + "/import",
+        method: "POST",
+        reqBody: all,
+        resBody: yxc.object({
+          message: yxc.string().equals("Request processed successfully"),
+          status: yxc.number().equals(202),
+          result: yxc.object({
+            num_imported: yxc.number().equals(3),
+            num_skipped: yxc.number().equals(0),
+          }),
+        }),
+      },
+      {
+        title: "Importing again should skip existing messages",
+        status: 202,
+        url: queueUri(targetName) + "/import",
+        method: "POST",
+        reqBody: all,
+        resBody: yxc.object({
+          message: yxc.string().equals("Request processed successfully"),
+          status: yxc.number().equals(202),
+          result: yxc.object({
+            num_imported: yxc.number().equals(0),
+            num_skipped: yxc.number().equals(3),
+          }),
+        }),
+      },
+      {
+        title: "Imported messages should keep their ids",
+        status: 200,
+        url: queueUri(targetName) + "/peek",
+        resBody: yxc.object({
+          message: yxc.string().equals("Message retrieved successfully"),
+          status: yxc.number().equals(200),
+          result: yxc.object({
+            item: Message(),
+          }),
+        }),
+        validate: async () => {
+          const res = await Axios.get(getUrl(queueUri(targetName) + "/peek"));
+          expect(res.data.result.item.id).to.equal(parseLines(all)[0].id);
+        },
+      },
+      {
+        title: "Invalid line -> 400",
+        status: 400,
+        url: queueUri(targetName) + "/import",
+        method: "POST",
+        reqBody: "{}",
+        resBody: yxc.object({
+          error: yxc.boolean().true(),
+          message: yxc.string().equals("Invalid message in line 1"),
+          status: yxc.number().equals(400),
+        }),
+      },
+    ],
+  };
+});
@@ -0,0 +1,43 @@
+import { defineWorkflow } from "voce";
+import yxc from "@dotvirus/yxc";
+import { IP } from "../util";
+
+const Check = () =>
+  yxc.object({
+    name: yxc
+      .string()
+      .enum(["queues_loaded", "data_folder_writable", "disk_space"]),
+    ok: yxc.boolean().true(),
+    message: yxc.string(),
+  });
+
+export default defineWorkflow(async () => {
+  return {
+    title: "Health checks",
+    baseUrl: IP,
+    steps: [
+      {
+        title: "Liveness",
+        status: 200,
+        url: "/health/live",
+        resBody: yxc.object({
+          message: yxc.string().equals("Server is alive"),
+          status: yxc.number().equals(200),
+          result: yxc.null(),
+        }),
+      },
+      {
+        title: "Readiness",
+        status: 200,
+        url: "/health/ready",
+        resBody: yxc.object({
+          message: yxc.string().equals("Server is ready"),
+          status: yxc.number().equals(200),
+          result: yxc.object({
+            checks: yxc.array(Check()).len(4),
+          }),
+        }),
+      },
+    ],
+  };
+});
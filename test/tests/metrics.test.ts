@@ -0,0 +1,54 @@
+import { defineWorkflow } from "voce";
+import { createQueue, dequeue, enqueue } from "../common";
+import { getUrl, IP } from "../util";
+import { expect } from "chai";
+import Axios from "axios";
+
+export default defineWorkflow(async () => {
+  const queueName = "metrics_test";
+
+  await createQueue(queueName);
+  await enqueue(queueName, [
+    { item: { index: 0 }, deduplication_id: "a" },
+    { item: { index: 1 }, deduplication_id: "a" },
+    { item: { index: 2 }, deduplication_id: null },
+  ]);
+  await dequeue(queueName);
+
+  return {
+    title: "Prometheus metrics",
+    baseUrl: IP,
+    steps: [
+      {
+        title: "Metrics should contain queue counters & request latencies",
+        status: 200,
+        url: "/metrics",
+        validate: async () => {
+          const res = await Axios.get(getUrl("/metrics"), {
+            responseType: "text",
+          });
+          expect(res.headers["content-type"]).to.contain("text/plain");
+          const lines: string[] = res.data.split("\n");
+
+          const sample = (name: string) =>
+            lines.find((line) =>
+              line.startsWith(`${name}{queue="${queueName}"} `)
+            );
+          expect(sample("corinth_queue_size")).to.match(/ 1$/);
+          expect(sample("corinth_queue_in_flight")).to.match(/ 1$/);
+          expect(sample("corinth_queue_enqueued_total")).to.match(/ 2$/);
+          expect(sample("corinth_queue_dequeued_total")).to.match(/ 1$/);
+          expect(sample("corinth_queue_deduplicated_total")).to.match(/ 1$/);
+
+          expect(
+            lines.some((line) =>
+              line.startsWith(
+                'corinth_http_request_duration_seconds_count{method="POST",route="/queue/{queue_name}/enqueue",status="202"}'
+              )
+            )
+          ).to.be.true;
+        },
+      },
+    ],
+  };
+});
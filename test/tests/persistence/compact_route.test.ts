@@ -1,5 +1,11 @@
 import { defineWorkflow, WorkflowStep } from "voce";
-import { countSync, getUrl, IP, persistenceTeardown } from "../../util";
+import {
+  countSync,
+  getUrl,
+  IP,
+  persistenceTeardown,
+  sleep,
+} from "../../util";
 import { createQueue, dequeue, Message, queueUri } from "../../common";
 import yxc from "@dotvirus/yxc";
 import { assert, expect } from "chai";
@@ -133,6 +139,8 @@ export default defineWorkflow(async () => {
           }),
         }),
         validate: async () => {
+          // Counters are flushed to disk periodically
+          await sleep(1500);
           expect(
             JSON.parse(
               readFileSync(".corinth/queues/compact_route/meta.json", "utf-8")
@@ -140,6 +140,8 @@ export default defineWorkflow(async () => {
           }),
         }),
         validate: async () => {
+          // Counters are flushed to disk periodically
+          await sleep(1500);
           expect(
             JSON.parse(
               readFileSync(".corinth/queues/compaction_test/meta.json", "utf-8")
@@ -174,6 +174,8 @@ export default defineWorkflow(async () => {
             await dequeue(queueName, true);
           }
 
+          // Counters are flushed to disk periodically
+          await sleep(1500);
           expect(
             JSON.parse(
               readFileSync(".corinth/queues/loadorder_test/meta.json", "utf-8")
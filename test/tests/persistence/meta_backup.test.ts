@@ -0,0 +1,93 @@
+import { defineWorkflow } from "voce";
+import {
+  getUrl,
+  IP,
+  persistenceTeardown,
+  sleep,
+  spawnCorinth,
+} from "../../util";
+import { createQueue, queueUri } from "../../common";
+import yxc from "@dotvirus/yxc";
+import { assert } from "chai";
+import { existsSync, writeFileSync } from "fs";
+import Axios from "axios";
+
+export default defineWorkflow(async () => {
+  persistenceTeardown();
+
+  const queueName = "meta_backup";
+  const queueUrl = queueUri(queueName);
+
+  await createQueue(queueName, {
+    params: {
+      persistent: "true",
+      requeue_time: 120,
+    },
+  });
+
+  assert(existsSync(".corinth/queues/meta_backup/meta.json"));
+  assert(!existsSync(".corinth/queues/meta_backup/meta.json.bak"));
+
+  // Rewrite metadata, so the previous version is kept as backup
+  await Axios.patch(getUrl(queueUrl), { deduplication_time: 60 });
+
+  assert(existsSync(".corinth/queues/meta_backup/meta.json.bak"));
+  assert(!existsSync(".corinth/queues/meta_backup/meta.json~"));
+
+  return {
+    title: "Metadata backup",
+    baseUrl: IP,
+    onSuccess: persistenceTeardown,
+    steps: [
+      {
+        title: "Queue should be edited",
+        status: 200,
+        url: queueUrl,
+        resBody: yxc.object({
+          message: yxc.string().equals("Queue info retrieved successfully"),
+          status: yxc.number().equals(200),
+          result: yxc.object({
+            queue: yxc
+              .object({
+                name: yxc.string().equals(queueName),
+                deduplication_time: yxc.number().equals(60),
+                requeue_time: yxc.number().equals(120),
+                persistent: yxc.boolean().true(),
+              })
+              .arbitrary(),
+          }),
+        }),
+        onSuccess: async () => {
+          await Axios.post(getUrl("/close"));
+          await sleep(3500);
+          // Simulate a crash in the middle of writing meta.json
+          writeFileSync(
+            ".corinth/queues/meta_backup/meta.json",
+            '{"created_at":16'
+          );
+          spawnCorinth();
+          await sleep(1000);
+        },
+      },
+      {
+        title: "Queue should be restored from backup metadata",
+        status: 200,
+        url: queueUrl,
+        resBody: yxc.object({
+          message: yxc.string().equals("Queue info retrieved successfully"),
+          status: yxc.number().equals(200),
+          result: yxc.object({
+            queue: yxc
+              .object({
+                name: yxc.string().equals(queueName),
+                deduplication_time: yxc.number().equals(300),
+                requeue_time: yxc.number().equals(120),
+                persistent: yxc.boolean().true(),
+              })
+              .arbitrary(),
+          }),
+        }),
+      },
+    ],
+  };
+});
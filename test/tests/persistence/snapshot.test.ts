@@ -0,0 +1,49 @@
+import { defineWorkflow } from "voce";
+import { getUrl, IP, persistenceTeardown } from "../../util";
+import { createQueue, dequeue, enqueue } from "../../common";
+import { expect } from "chai";
+import Axios from "axios";
+
+export default defineWorkflow(async () => {
+  persistenceTeardown();
+
+  const queueName = "snapshot_test";
+
+  await createQueue(queueName, {
+    params: {
+      persistent: "true",
+    },
+  });
+  await createQueue("snapshot_volatile");
+
+  await enqueue(queueName, [
+    { item: { index: 0 }, deduplication_id: null },
+    { item: { index: 1 }, deduplication_id: null },
+  ]);
+  await dequeue(queueName);
+
+  return {
+    title: "Snapshot",
+    baseUrl: IP,
+    onSuccess: persistenceTeardown,
+    steps: [
+      {
+        title: "Snapshot should contain persistent queues only",
+        status: 200,
+        url: "/snapshot",
+        validate: async () => {
+          const res = await Axios.get(getUrl("/snapshot"), {
+            responseType: "arraybuffer",
+          });
+          expect(res.headers["content-type"]).to.equal("application/x-tar");
+
+          const archive = Buffer.from(res.data).toString("latin1");
+          expect(archive).to.include("queues/snapshot_test/meta.json");
+          expect(archive).to.include("queues/snapshot_test/items.jsonl");
+          expect(archive).to.include("queues/snapshot_test/inflight.jsonl");
+          expect(archive).not.to.include("queues/snapshot_volatile");
+        },
+      },
+    ],
+  };
+});
@@ -0,0 +1,91 @@
+import { defineWorkflow } from "voce";
+import {
+  getUrl,
+  IP,
+  PORT,
+  persistenceTeardown,
+  sleep,
+  spawnCorinth,
+} from "../../util";
+import { createQueue, enqueue, queueUri } from "../../common";
+import yxc from "@dotvirus/yxc";
+import { assert } from "chai";
+import { existsSync } from "fs";
+import Axios from "axios";
+
+async function restartCorinth() {
+  await Axios.post(getUrl("/close"));
+  await sleep(3500);
+  await spawnCorinth(PORT, 0, {
+    CORINTH_VOLATILE_SNAPSHOT: "contents",
+  });
+}
+
+export default defineWorkflow(async () => {
+  persistenceTeardown();
+
+  const queueName = "volatile_snapshot";
+  const queueUrl = queueUri(queueName);
+
+  await restartCorinth();
+
+  await createQueue(queueName, {
+    params: {
+      requeue_time: 120,
+    },
+  });
+  await enqueue(queueName, [
+    { item: { index: 0 }, deduplication_id: null },
+    { item: { index: 1 }, deduplication_id: null },
+  ]);
+
+  return {
+    title: "Volatile queue snapshot",
+    baseUrl: IP,
+    onSuccess: persistenceTeardown,
+    steps: [
+      {
+        title: "Queue should be volatile",
+        status: 200,
+        url: queueUrl,
+        resBody: yxc.object({
+          message: yxc.string().equals("Queue info retrieved successfully"),
+          status: yxc.number().equals(200),
+          result: yxc.object({
+            queue: yxc
+              .object({
+                name: yxc.string().equals(queueName),
+                size: yxc.number().equals(2),
+                persistent: yxc.boolean().false(),
+              })
+              .arbitrary(),
+          }),
+        }),
+        onSuccess: async () => {
+          await restartCorinth();
+          // Loaded snapshot file is removed
+          assert(!existsSync(".corinth/volatile.json"));
+        },
+      },
+      {
+        title: "Queue should be recreated with its messages",
+        status: 200,
+        url: queueUrl,
+        resBody: yxc.object({
+          message: yxc.string().equals("Queue info retrieved successfully"),
+          status: yxc.number().equals(200),
+          result: yxc.object({
+            queue: yxc
+              .object({
+                name: yxc.string().equals(queueName),
+                size: yxc.number().equals(2),
+                requeue_time: yxc.number().equals(120),
+                persistent: yxc.boolean().false(),
+              })
+              .arbitrary(),
+          }),
+        }),
+      },
+    ],
+  };
+});
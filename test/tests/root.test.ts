@@ -1,8 +1,13 @@
+import * as toml from "toml";
+import { readFileSync } from "fs";
 import yxc from "@dotvirus/yxc";
 import { IP, persistenceTeardown } from "../util";
 import { defineWorkflow } from "voce";
 
 export default defineWorkflow(async () => {
+  const cargoVersion: string = toml.parse(readFileSync("Cargo.toml", "utf-8"))
+    .package.version;
+
   return {
     title: "Root",
     baseUrl: IP,
@@ -18,10 +23,18 @@ export default defineWorkflow(async () => {
           result: yxc.object({
             info: yxc.object({
               name: yxc.string().equals("Corinth"),
-              version: yxc.string().equals("0.4.0"),
+              version: yxc.string().equals(cargoVersion),
               started_at: yxc.number().natural(),
               uptime_secs: yxc.number().natural({ withZero: true }),
               uptime_ms: yxc.number().natural({ withZero: true }),
+              build: yxc.object({
+                commit: yxc.string(),
+                built_at: yxc.number().natural(),
+              }),
+              storage: yxc.object({
+                format_version: yxc.number().natural(),
+                data_folder: yxc.string(),
+              }),
             }),
           }),
         }),
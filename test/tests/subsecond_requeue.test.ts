@@ -0,0 +1,69 @@
+import { defineWorkflow } from "voce";
+import {
+  advanceClock,
+  createQueue,
+  dequeue,
+  enqueue,
+  queueUri,
+} from "../common";
+import { getUrl, IP } from "../util";
+import { expect } from "chai";
+import Axios from "axios";
+import yxc from "@dotvirus/yxc";
+
+export default defineWorkflow(async () => {
+  const queueName = "subsecond_requeue";
+  const queueUrl = queueUri(queueName);
+
+  await createQueue(queueName, {
+    params: {
+      requeue_time: 0.5,
+    },
+  });
+  const before = Date.now();
+  await enqueue(queueName, [
+    { item: { index: 0 }, deduplication_id: null },
+    { item: { index: 1 }, deduplication_id: null },
+  ]);
+
+  return {
+    title: "Sub-second requeue time & millisecond timestamps",
+    baseUrl: IP,
+    steps: [
+      {
+        title: "Timestamps should be in milliseconds",
+        status: 200,
+        url: queueUrl + "/peek",
+        validate: async () => {
+          const { data } = await Axios.get(getUrl(queueUrl + "/peek"));
+          const { queued_at, updated_at } = data.result.item;
+          expect(queued_at).to.be.within(before - 1000, Date.now() + 1000);
+          expect(updated_at).to.equal(queued_at);
+        },
+      },
+      {
+        title: "Unacknowledged message should be requeued after 0.5 secs",
+        status: 200,
+        url: queueUrl,
+        onBefore: async () => {
+          await dequeue(queueName);
+          await advanceClock(500);
+        },
+        resBody: yxc.object({
+          message: yxc.string().equals("Queue info retrieved successfully"),
+          status: yxc.number().equals(200),
+          result: yxc.object({
+            queue: yxc
+              .object({
+                requeue_time: yxc.number().equals(0.5),
+                num_requeued: yxc.number().equals(1),
+                num_unacknowledged: yxc.number().equals(0),
+                size: yxc.number().equals(2),
+              })
+              .arbitrary(),
+          }),
+        }),
+      },
+    ],
+  };
+});
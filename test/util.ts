@@ -56,7 +56,11 @@ export function sleep(ms: number) {
   return new Promise((r) => setTimeout(r, ms));
 }
 
-export async function spawnCorinth(port = PORT, interval: number = 0) {
+export async function spawnCorinth(
+  port = PORT,
+  interval: number = 0,
+  env: Record<string, string> = {}
+) {
   const exeName = executableName("corinth");
   const path = `./target/debug/${exeName}`;
   logMessage(`Spawning ${path} with port ${port}`);
@@ -64,6 +68,8 @@ export async function spawnCorinth(port = PORT, interval: number = 0) {
     env: {
       CORINTH_PORT: port.toString(),
       CORINTH_COMPACT_INTERVAL: interval.toString(),
+      CORINTH_META_FLUSH_INTERVAL: "1",
+      ...env,
     },
     stdio: "pipe",
   });
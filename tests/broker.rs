@@ -0,0 +1,159 @@
+// Uses the queue engine as a library, without the HTTP server
+use corinth::{Broker, BrokerError, BrokerOptions, NewMessage, QueueSettings};
+use serde_json::json;
+use tempfile::TempDir;
+
+fn broker(data_folder: &TempDir) -> Broker {
+  Broker::new(BrokerOptions {
+    data_folder: data_folder.path().to_str().unwrap().to_string(),
+    ..BrokerOptions::default()
+  })
+}
+
+fn volatile() -> QueueSettings {
+  QueueSettings {
+    persistent: false,
+    ..QueueSettings::default()
+  }
+}
+
+fn message(n: u64) -> NewMessage {
+  NewMessage {
+    item: json!({ "n": n }),
+    deduplication_id: None,
+  }
+}
+
+#[test]
+fn enqueue_dequeue_ack() {
+  let folder = tempfile::tempdir().unwrap();
+  let broker = broker(&folder);
+
+  broker.create_queue("jobs", volatile()).unwrap();
+  let result = broker
+    .enqueue("jobs", vec![message(1), message(2)])
+    .unwrap();
+  assert_eq!(result.enqueued.len(), 2);
+  assert_eq!(result.num_deduplicated, 0);
+
+  let head = broker.peek("jobs").unwrap().unwrap();
+  assert_eq!(head.item(), json!({ "n": 1 }));
+
+  let messages = broker.dequeue("jobs", 5, false).unwrap();
+  assert_eq!(messages.len(), 2);
+  assert_eq!(broker.queues()["jobs"].ack_size(), 2);
+
+  broker.ack("jobs", messages[0].id()).unwrap();
+  assert_eq!(
+    broker.ack("jobs", messages[0].id()),
+    Err(BrokerError::MessageNotFound)
+  );
+  assert_eq!(broker.queues()["jobs"].num_acknowledged(), 1);
+}
+
+#[test]
+fn errors() {
+  let folder = tempfile::tempdir().unwrap();
+  let broker = broker(&folder);
+
+  assert_eq!(
+    broker.enqueue("missing", vec![message(1)]).err(),
+    Some(BrokerError::QueueNotFound)
+  );
+  assert_eq!(
+    broker.dequeue("missing", 1, true).err(),
+    Some(BrokerError::QueueNotFound)
+  );
+
+  broker
+    .create_queue(
+      "bounded",
+      QueueSettings {
+        max_length: 1,
+        ..volatile()
+      },
+    )
+    .unwrap();
+  assert_eq!(
+    broker.create_queue("bounded", volatile()),
+    Err(BrokerError::QueueAlreadyExists)
+  );
+  assert_eq!(
+    broker
+      .enqueue("bounded", vec![message(1), message(2)])
+      .err(),
+    Some(BrokerError::QueueFull)
+  );
+}
+
+#[test]
+fn dead_letter_queue_in_use() {
+  let folder = tempfile::tempdir().unwrap();
+  let broker = broker(&folder);
+
+  let settings = QueueSettings {
+    dead_letter_queue: Some(corinth::QueueDeadLetterSettings {
+      name: String::from("dead"),
+      threshold: 3,
+    }),
+    ..volatile()
+  };
+  assert_eq!(
+    broker.create_queue("failing", settings.clone()),
+    Err(BrokerError::DeadLetterTargetNotFound)
+  );
+
+  broker.create_queue("dead", volatile()).unwrap();
+  broker.create_queue("failing", settings).unwrap();
+  assert_eq!(
+    broker.delete_queue("dead"),
+    Err(BrokerError::DeadLetterQueueInUse)
+  );
+  broker.delete_queue("failing").unwrap();
+  broker.delete_queue("dead").unwrap();
+  assert!(broker.queues().is_empty());
+}
+
+#[test]
+fn separate_brokers() {
+  let first_folder = tempfile::tempdir().unwrap();
+  let second_folder = tempfile::tempdir().unwrap();
+  let first = broker(&first_folder);
+  let second = broker(&second_folder);
+
+  first.create_queue("shared_name", volatile()).unwrap();
+  second.create_queue("shared_name", volatile()).unwrap();
+  first.enqueue("shared_name", vec![message(1)]).unwrap();
+
+  assert_eq!(first.queues()["shared_name"].size(), 1);
+  assert_eq!(second.queues()["shared_name"].size(), 0);
+
+  // Clones share the queues
+  assert_eq!(first.clone().queues()["shared_name"].size(), 1);
+}
+
+#[test]
+fn persistence() {
+  let folder = tempfile::tempdir().unwrap();
+  {
+    let broker = broker(&folder);
+    broker
+      .create_queue("stored", QueueSettings::default())
+      .unwrap();
+    broker
+      .enqueue("stored", vec![message(1), message(2), message(3)])
+      .unwrap();
+    broker.dequeue("stored", 1, true).unwrap();
+    broker.flush();
+  }
+
+  let broker = broker(&folder);
+  assert!(!broker.queue_exists("stored"));
+  broker.load();
+
+  assert_eq!(broker.queues()["stored"].size(), 2);
+  assert_eq!(broker.queues()["stored"].num_acknowledged(), 1);
+  let messages = broker.dequeue("stored", 2, true).unwrap();
+  assert_eq!(messages[0].item(), json!({ "n": 2 }));
+  assert_eq!(messages[1].item(), json!({ "n": 3 }));
+}
@@ -0,0 +1,198 @@
+// Inspects & repairs the files of stopped brokers
+use corinth::storage::{
+  compact_queue, inspect_queue, lock_data_folder, read_queue_messages, repair_queue, stored_queues,
+  MetaStatus, StorageError,
+};
+use corinth::{Broker, BrokerOptions, NewMessage, QueueSettings};
+use serde_json::json;
+use std::fs::{read_to_string, write, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use tempfile::TempDir;
+
+// Stores a queue with 3 enqueued & 1 dequeued message
+fn stored_queue(name: &str) -> (TempDir, String) {
+  let folder = tempfile::tempdir().unwrap();
+  let data_folder = folder.path().to_str().unwrap().to_string();
+  let broker = Broker::new(BrokerOptions {
+    data_folder: data_folder.clone(),
+    ..BrokerOptions::default()
+  });
+  broker.create_queue(name, QueueSettings::default()).unwrap();
+  let messages = (1..=3)
+    .map(|n| NewMessage::new(json!({ "n": n })))
+    .collect();
+  broker.enqueue(name, messages).unwrap();
+  broker.dequeue(name, 1, true).unwrap();
+  broker.flush();
+  (folder, data_folder)
+}
+
+fn queue_file(data_folder: &str, name: &str, file: &str) -> PathBuf {
+  PathBuf::from(data_folder)
+    .join("queues")
+    .join(name)
+    .join(file)
+}
+
+fn append(path: &PathBuf, text: &str) {
+  let mut file = OpenOptions::new().append(true).open(path).unwrap();
+  file.write_all(text.as_bytes()).unwrap();
+}
+
+// Loads the queues like the server does on start
+fn load(data_folder: &str) -> Broker {
+  let broker = Broker::new(BrokerOptions {
+    data_folder: String::from(data_folder),
+    ..BrokerOptions::default()
+  });
+  broker.load();
+  broker
+}
+
+#[test]
+fn inspect_healthy_queue() {
+  let (_folder, data_folder) = stored_queue("jobs");
+
+  assert_eq!(stored_queues(&data_folder).unwrap(), vec!["jobs"]);
+  let report = inspect_queue(&data_folder, "jobs").unwrap();
+  assert!(report.is_healthy());
+  assert_eq!(report.meta.status, MetaStatus::Valid);
+  assert_eq!(report.items.num_records, 4);
+  assert_eq!(report.items.num_live, 2);
+  assert_eq!(report.items.num_deleted, 1);
+  assert_eq!(report.items.num_corrupt, 0);
+
+  let messages = read_queue_messages(&data_folder, "jobs").unwrap();
+  assert_eq!(messages[0].item(), json!({ "n": 2 }));
+  assert_eq!(messages[1].item(), json!({ "n": 3 }));
+
+  assert!(matches!(
+    inspect_queue(&data_folder, "missing"),
+    Err(StorageError::QueueNotFound)
+  ));
+}
+
+#[test]
+fn compact_offline() {
+  let (_folder, data_folder) = stored_queue("jobs");
+
+  assert_eq!(compact_queue(&data_folder, "jobs").unwrap(), 2);
+  let report = inspect_queue(&data_folder, "jobs").unwrap();
+  assert_eq!(report.items.num_records, 2);
+  assert_eq!(report.items.num_deleted, 0);
+  assert_eq!(report.items.num_live, 2);
+}
+
+#[test]
+fn refuse_locked_data_folder() {
+  let (_folder, data_folder) = stored_queue("jobs");
+
+  let lock = lock_data_folder(&data_folder).unwrap();
+  assert!(matches!(
+    lock_data_folder(&data_folder),
+    Err(StorageError::DataFolderLocked)
+  ));
+  assert!(matches!(
+    compact_queue(&data_folder, "jobs"),
+    Err(StorageError::DataFolderLocked)
+  ));
+  assert!(matches!(
+    repair_queue(&data_folder, "jobs", false),
+    Err(StorageError::DataFolderLocked)
+  ));
+
+  drop(lock);
+  assert_eq!(compact_queue(&data_folder, "jobs").unwrap(), 2);
+}
+
+#[test]
+fn repair_bad_tail() {
+  let (_folder, data_folder) = stored_queue("jobs");
+  let items = queue_file(&data_folder, "jobs", "items.jsonl");
+  let valid_size = read_to_string(&items).unwrap().len() as u64;
+  // Interrupted write
+  append(&items, "{\"id\":\"01");
+
+  let report = inspect_queue(&data_folder, "jobs").unwrap();
+  assert!(!report.is_healthy());
+  assert_eq!(report.items.num_corrupt, 1);
+  let corrupt = report.items.first_corrupt.unwrap();
+  assert_eq!(corrupt.line, 5);
+  assert_eq!(corrupt.offset, valid_size);
+  assert!(matches!(
+    compact_queue(&data_folder, "jobs"),
+    Err(StorageError::CorruptRecords)
+  ));
+
+  let repair = repair_queue(&data_folder, "jobs", false).unwrap();
+  assert_eq!(repair.num_truncated_bytes, 9);
+  assert_eq!(
+    read_to_string(repair.tail_file.unwrap()).unwrap(),
+    "{\"id\":\"01"
+  );
+  assert!(inspect_queue(&data_folder, "jobs").unwrap().is_healthy());
+
+  let broker = load(&data_folder);
+  assert_eq!(broker.queues()["jobs"].size(), 2);
+}
+
+#[test]
+fn repair_corruption_in_the_middle() {
+  let (_folder, data_folder) = stored_queue("jobs");
+  let items = queue_file(&data_folder, "jobs", "items.jsonl");
+  append(&items, "garbage\n{\"$corinth_deleted\":\"x\" }\n");
+
+  let report = inspect_queue(&data_folder, "jobs").unwrap();
+  assert_eq!(report.items.num_valid_after_corrupt, 1);
+  assert!(matches!(
+    repair_queue(&data_folder, "jobs", false),
+    Err(StorageError::ValidRecordsAfterCorruption {
+      line: 5,
+      num_valid: 1
+    })
+  ));
+
+  repair_queue(&data_folder, "jobs", true).unwrap();
+  let report = inspect_queue(&data_folder, "jobs").unwrap();
+  assert!(report.is_healthy());
+  assert_eq!(report.items.num_live, 2);
+}
+
+#[test]
+fn repair_metadata() {
+  let (_folder, data_folder) = stored_queue("jobs");
+  let meta = queue_file(&data_folder, "jobs", "meta.json");
+  let backup = queue_file(&data_folder, "jobs", "meta.json.bak");
+
+  write(&meta, "{").unwrap();
+  let report = inspect_queue(&data_folder, "jobs").unwrap();
+  assert_eq!(report.meta.status, MetaStatus::Backup);
+  assert!(!report.is_healthy());
+  assert!(
+    repair_queue(&data_folder, "jobs", false)
+      .unwrap()
+      .restored_meta_from_backup
+  );
+  assert_eq!(
+    inspect_queue(&data_folder, "jobs").unwrap().meta.status,
+    MetaStatus::Valid
+  );
+
+  write(&meta, "{").unwrap();
+  write(&backup, "").unwrap();
+  let report = inspect_queue(&data_folder, "jobs").unwrap();
+  assert_eq!(report.meta.status, MetaStatus::Corrupt);
+  assert!(matches!(
+    repair_queue(&data_folder, "jobs", false),
+    Err(StorageError::MetadataUnrecoverable)
+  ));
+  assert!(
+    repair_queue(&data_folder, "jobs", true)
+      .unwrap()
+      .recreated_meta
+  );
+
+  let broker = load(&data_folder);
+  assert_eq!(broker.queues()["jobs"].size(), 2);
+}